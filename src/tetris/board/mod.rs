@@ -0,0 +1,278 @@
+use crate::tetris::{Block, Cell};
+
+/// Each row is a bitmask with bit x set when the block in column x is filled, so collisions and
+/// line clears are a few bit operations a row. Which shape filled each block is kept alongside,
+/// row by row
+pub struct Board {
+    width: i8,
+    height: i8,
+    vanish_rows: i8,
+    full_row: u64,
+    rows: Vec<u64>,
+    cells: Vec<Cell>,
+}
+
+/// The visible rows run from 0 down to height - 1, with the hidden vanish zone rows above them
+/// numbered from -1 up to -vanish_rows
+impl Board {
+    /// # Panics
+    ///
+    /// If the width or height is outside 4 to 64, or there are more than 40 vanish rows
+    pub fn new(width: u8, height: u8, vanish_rows: u8) -> Board {
+        assert!((4..=64).contains(&width), "board width must be between 4 and 64");
+        assert!((4..=64).contains(&height), "board height must be between 4 and 64");
+        assert!(vanish_rows <= 40, "board vanish zone must be at most 40 rows");
+        Board {
+            width: width as i8,
+            height: height as i8,
+            vanish_rows: vanish_rows as i8,
+            full_row: u64::MAX >> (64 - width),
+            rows: vec![0; usize::from(height + vanish_rows)],
+            cells: vec![Cell::Empty; usize::from(height + vanish_rows) * usize::from(width)],
+        }
+    }
+
+    pub fn width(&self) -> i8 {
+        self.width
+    }
+
+    pub fn height(&self) -> i8 {
+        self.height
+    }
+
+    pub fn vanish_rows(&self) -> i8 {
+        self.vanish_rows
+    }
+
+    pub fn is_filled(&self, x: i8, y: i8) -> bool {
+        self.is_on_grid(x, y) && self.rows[self.row(y)] & 1 << x != 0
+    }
+
+    pub fn cell_at(&self, x: i8, y: i8) -> Cell {
+        if self.is_filled(x, y) {
+            self.cells[self.cell(x, y)]
+        } else {
+            Cell::Empty
+        }
+    }
+
+    /// Whether a block can't go at the given position, because it's filled or off the grid
+    pub fn is_blocked(&self, x: i8, y: i8) -> bool {
+        !self.is_on_grid(x, y) || self.rows[self.row(y)] & 1 << x != 0
+    }
+
+    pub fn fits(&self, blocks: &[Block; 4]) -> bool {
+        match Board::masks(blocks, self.width) {
+            Some((top, masks)) => self.masks_fit(top, &masks),
+            None => false,
+        }
+    }
+
+    /// How many rows the blocks can fall before landing on the stack or the floor
+    pub fn drop_distance(&self, blocks: &[Block; 4]) -> i8 {
+        let (top, masks) = match Board::masks(blocks, self.width) {
+            Some(top_and_masks) => top_and_masks,
+            None => return 0,
+        };
+        let mut distance = 0;
+        while self.masks_fit(top + distance + 1, &masks) {
+            distance += 1;
+        }
+        distance
+    }
+
+    pub fn fill(&mut self, blocks: &[Block; 4], cell: Cell) {
+        for block in blocks {
+            let row = self.row(block.y());
+            self.rows[row] |= 1 << block.x();
+            let index = self.cell(block.x(), block.y());
+            self.cells[index] = cell;
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rows.iter().all(|row| *row == 0)
+    }
+
+    /// Removes any complete lines, dropping the lines above, and returns the rows removed
+    pub fn clear_lines(&mut self) -> Vec<i8> {
+        let cleared_rows: Vec<i8> = (-self.vanish_rows..self.height).rev()
+            .filter(|y| self.rows[self.row(*y)] == self.full_row)
+            .collect();
+        if !cleared_rows.is_empty() {
+            let full_row = self.full_row;
+            let width = self.width as usize;
+            let mut cells = vec![Cell::Empty; cleared_rows.len() * width];
+            for (row, row_cells) in self.rows.iter().zip(self.cells.chunks(width)) {
+                if *row != full_row {
+                    cells.extend_from_slice(row_cells);
+                }
+            }
+            self.cells = cells;
+            self.rows.retain(|row| *row != full_row);
+            self.rows.splice(0..0, cleared_rows.iter().map(|_| 0));
+        }
+        cleared_rows
+    }
+
+    /// The blocks as row masks from the top row down, or nothing if any are off the sides
+    fn masks(blocks: &[Block; 4], width: i8) -> Option<(i8, [u64; 4])> {
+        if blocks.iter().any(|block| !(0..width).contains(&block.x())) {
+            return None;
+        }
+        let top = blocks.iter().map(|block| block.y()).min().unwrap();
+        let mut masks = [0; 4];
+        for block in blocks {
+            masks[(block.y() - top) as usize] |= 1 << block.x();
+        }
+        Some((top, masks))
+    }
+
+    fn masks_fit(&self, top: i8, masks: &[u64; 4]) -> bool {
+        masks.iter().zip(top..).all(|(mask, y)| {
+            *mask == 0 || ((-self.vanish_rows..self.height).contains(&y) && self.rows[self.row(y)] & mask == 0)
+        })
+    }
+
+    fn is_on_grid(&self, x: i8, y: i8) -> bool {
+        (0..self.width).contains(&x) && (-self.vanish_rows..self.height).contains(&y)
+    }
+
+    fn row(&self, y: i8) -> usize {
+        (y + self.vanish_rows) as usize
+    }
+
+    fn cell(&self, x: i8, y: i8) -> usize {
+        self.row(y) * self.width as usize + x as usize
+    }
+}
+
+impl Clone for Board {
+    fn clone(&self) -> Self {
+        Board {
+            width: self.width,
+            height: self.height,
+            vanish_rows: self.vanish_rows,
+            full_row: self.full_row,
+            rows: self.rows.clone(),
+            cells: self.cells.clone(),
+        }
+    }
+
+    // restoring snapshots happens a lot in searches, so reuse the rows already allocated
+    fn clone_from(&mut self, source: &Self) {
+        self.width = source.width;
+        self.height = source.height;
+        self.vanish_rows = source.vanish_rows;
+        self.full_row = source.full_row;
+        self.rows.clone_from(&source.rows);
+        self.cells.clone_from(&source.cells);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_treat_off_grid_as_blocked() {
+        // given
+        let board = Board::new(4, 6, 0);
+
+        // when / then
+        assert!(!board.is_blocked(3, 5));
+        assert!(board.is_blocked(4, 5));
+        assert!(board.is_blocked(3, 6));
+        assert!(board.is_blocked(-1, 0));
+        assert!(!board.is_filled(4, 5));
+    }
+
+    #[test]
+    fn should_clear_complete_lines_and_drop_the_rest() {
+        // given
+        let mut board = Board::new(4, 6, 0);
+        board.fill(&[Block::new(0, 5), Block::new(1, 5), Block::new(2, 5), Block::new(3, 5)], Cell::Garbage);
+        board.fill(&[Block::new(0, 4), Block::new(0, 3), Block::new(1, 3), Block::new(2, 3)], Cell::Garbage);
+        board.fill(&[Block::new(3, 3), Block::new(1, 2), Block::new(0, 1), Block::new(1, 1)], Cell::Garbage);
+
+        // when
+        let cleared_rows = board.clear_lines();
+
+        // then
+        assert_eq!(vec![5, 3], cleared_rows);
+        assert!(board.is_filled(0, 5));
+        assert!(board.is_filled(1, 4));
+        assert!(board.is_filled(0, 3));
+        assert!(board.is_filled(1, 3));
+        assert!(!board.is_filled(1, 5));
+        assert!(!board.is_filled(0, 2));
+    }
+
+    #[test]
+    fn should_be_empty_after_clearing_every_line() {
+        // given
+        let mut board = Board::new(4, 6, 0);
+        board.fill(&[Block::new(0, 5), Block::new(1, 5), Block::new(2, 5), Block::new(3, 5)], Cell::Garbage);
+
+        // when
+        board.clear_lines();
+
+        // then
+        assert!(board.is_empty());
+    }
+
+    #[test]
+    fn should_hold_blocks_in_the_vanish_zone() {
+        // given
+        let mut board = Board::new(4, 6, 2);
+
+        // when
+        board.fill(&[Block::new(0, -2), Block::new(0, -1), Block::new(0, 0), Block::new(1, 0)], Cell::Garbage);
+
+        // then
+        assert!(board.is_filled(0, -2));
+        assert!(board.is_filled(0, -1));
+        assert!(!board.is_blocked(1, -2));
+        assert!(board.is_blocked(1, -3));
+    }
+
+    #[test]
+    fn should_measure_drop_distance_onto_the_stack() {
+        // given
+        let mut board = Board::new(4, 6, 0);
+        board.fill(&[Block::new(0, 5), Block::new(1, 5), Block::new(1, 4), Block::new(1, 3)], Cell::Garbage);
+
+        // when / then
+        assert_eq!(3, board.drop_distance(&[Block::new(0, 0), Block::new(0, 1), Block::new(2, 1), Block::new(3, 1)]));
+        assert_eq!(2, board.drop_distance(&[Block::new(0, 0), Block::new(1, 0), Block::new(2, 0), Block::new(3, 0)]));
+        assert_eq!(0, board.drop_distance(&[Block::new(1, 2), Block::new(2, 2), Block::new(2, 1), Block::new(3, 1)]));
+    }
+
+    #[test]
+    fn should_not_fit_off_the_sides() {
+        // given
+        let board = Board::new(4, 6, 0);
+
+        // when / then
+        assert!(!board.fits(&[Block::new(1, 0), Block::new(2, 0), Block::new(3, 0), Block::new(4, 0)]));
+        assert!(!board.fits(&[Block::new(-1, 0), Block::new(0, 0), Block::new(1, 0), Block::new(2, 0)]));
+        assert!(board.fits(&[Block::new(0, 0), Block::new(1, 0), Block::new(2, 0), Block::new(3, 0)]));
+    }
+
+    #[test]
+    fn should_keep_the_cells_with_their_rows_when_clearing_lines() {
+        // given
+        let mut board = Board::new(4, 6, 0);
+        board.fill(&[Block::new(0, 5), Block::new(1, 5), Block::new(2, 5), Block::new(3, 5)], Cell::I);
+        board.fill(&[Block::new(0, 4), Block::new(1, 4), Block::new(0, 3), Block::new(1, 3)], Cell::O);
+
+        // when
+        board.clear_lines();
+
+        // then
+        assert_eq!(Cell::O, board.cell_at(0, 5));
+        assert_eq!(Cell::O, board.cell_at(1, 4));
+        assert_eq!(Cell::Empty, board.cell_at(2, 5));
+        assert_eq!(Cell::Empty, board.cell_at(0, 3));
+    }
+}
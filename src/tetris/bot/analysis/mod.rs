@@ -14,29 +14,31 @@ pub fn analyse(tetris: &Tetris) -> Analysis {
     let mut previous_column_height = 0;
     let mut current_column_height;
     let mut low_edges = 0;
-    for x in 0u8..10u8 {
+    let width = tetris.width();
+    let height = tetris.height();
+    for x in 0..width {
         current_column_height = 0;
         let mut column_has_higher_block = false;
-        for y in 0u8..20u8 {
-            let block_present = tetris.dead_blocks[usize::from(x)][usize::from(y)];
+        for y in -tetris.vanish_rows()..height {
+            let block_present = tetris.dead_blocks.is_filled(x, y);
             if block_present {
                 if column_has_higher_block == false {
-                    current_column_height = 20 - y;
+                    current_column_height = (height - y) as u8;
                 }
-                if x > 2 && x < 7 && central_columns_max_height < current_column_height {
+                if x > 2 && x < width - 3 && central_columns_max_height < current_column_height {
                     central_columns_max_height = current_column_height;
                 }
                 column_has_higher_block = true;
             } else if column_has_higher_block {
                 gaps = gaps + 1;
             }
-            if y == 19 {
+            if y == height - 1 {
                 if x > 0 {
                     total_neighbour_diff = total_neighbour_diff + current_column_height.abs_diff(previous_column_height);
                 }
                 if x == 1 && current_column_height > previous_column_height + 1 {
                     low_edges = low_edges + current_column_height - previous_column_height;
-                } else if x == 9 && previous_column_height > current_column_height + 1 {
+                } else if x == width - 1 && previous_column_height > current_column_height + 1 {
                     low_edges = low_edges + previous_column_height - current_column_height;
                 }
                 previous_column_height = current_column_height;
@@ -4,6 +4,11 @@ use clap::Parser;
 use tetris_rust::tetris::bot::Bot;
 use tetris_rust::tetris::bot::strategy::qlearning::QLearning;
 use tetris_rust::tetris::bot::strategy::weighted::Weighted;
+use tetris_rust::tetris::Config;
+use tetris_rust::tetris::randomizer;
+use tetris_rust::tetris::rotation::RotationSystem;
+use tetris_rust::tetris::scoring::ScoringSystem;
+use tetris_rust::tetris::timing::{SpeedCurve, Timing};
 
 #[derive(Parser)]
 struct BotArgs {
@@ -19,15 +24,54 @@ struct BotArgs {
     /// How many games to play
     #[arg(short, long, default_value_t = 1)]
     games: u32,
+    /// Seed for the shape sequence, so strategies can be compared on the same games
+    #[arg(long)]
+    seed: Option<u64>,
+    /// The randomizer name: bag, random, nes or tgm
+    #[arg(short, long, default_value = "bag")]
+    randomizer: String,
+    /// The scoring system name: guideline or nes
+    #[arg(long, default_value = "guideline")]
+    scoring: String,
+    /// The rotation system name: srs or classic
+    #[arg(long, default_value = "srs")]
+    rotation: String,
+    /// How many blocks wide the board is
+    #[arg(long, default_value_t = 10, value_parser = clap::value_parser!(u8).range(4..=64))]
+    width: u8,
+    /// How many blocks high the board is
+    #[arg(long, default_value_t = 20, value_parser = clap::value_parser!(u8).range(4..=64))]
+    height: u8,
+    /// How many hidden rows there are above the board for shapes to spawn into
+    #[arg(long, default_value_t = 20, value_parser = clap::value_parser!(u8).range(..=40))]
+    vanish_rows: u8,
+    /// The speed curve name: guideline, nes or tgm, to play in simulated real time rather than
+    /// move by move
+    #[arg(long)]
+    speed_curve: Option<String>,
 }
 
 fn main() {
     let bot_args = BotArgs::parse();
+    let config = Config {
+        randomizer: randomizer::from_name(&bot_args.randomizer).expect("not a known randomizer"),
+        seed: bot_args.seed,
+        scoring: ScoringSystem::from_name(&bot_args.scoring).expect("not a known scoring system"),
+        rotation_system: RotationSystem::from_name(&bot_args.rotation).expect("not a known rotation system"),
+        width: bot_args.width,
+        height: bot_args.height,
+        vanish_rows: bot_args.vanish_rows,
+        timing: bot_args.speed_curve.map(|speed_curve| Timing {
+            speed_curve: SpeedCurve::from_name(&speed_curve).expect("not a known speed curve"),
+            ..Timing::default()
+        }),
+        ..Config::default()
+    };
     if bot_args.strategy == "weighted" {
-        Bot::new().run(&mut Weighted::new(), bot_args.action_pause, bot_args.debug, bot_args.games)
+        Bot::new(config).run(&mut Weighted::new(), bot_args.action_pause, bot_args.debug, bot_args.games)
     }
     else if bot_args.strategy == "qlearning" {
-        Bot::new().run(&mut QLearning::new(), bot_args.action_pause, bot_args.debug, bot_args.games)
+        Bot::new(config).run(&mut QLearning::new(), bot_args.action_pause, bot_args.debug, bot_args.games)
     } else {
         panic!("not a known strategy")
     }
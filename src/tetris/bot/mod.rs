@@ -1,46 +1,65 @@
 use std::{thread, time};
 use std::io::{stdout, Write};
+use std::time::Duration;
 use crossterm::{cursor, ExecutableCommand, QueueableCommand, terminal};
+use crossterm::style::{Color, Stylize};
 use crate::tetris::bot::strategy::Strategy;
-use crate::tetris::{ActionResult, Tetris};
+use crate::tetris::{ActionResult, Block, Cell, Config, Tetris};
 
 pub mod analysis;
 pub mod strategy;
 
-pub struct Bot {}
+// bots playing games with timing press one input a frame
+const FRAME: Duration = Duration::from_nanos(16_666_667);
+
+pub struct Bot {
+    config: Config,
+}
 
 impl Bot {
-    pub fn new() -> Bot {
-        Bot {}
+    pub fn new(config: Config) -> Bot {
+        Bot { config }
     }
 
     pub fn run(&self, strategy: &mut dyn Strategy, action_pause: u64, debug: bool, games: u32) {
-        for _ in 0..games {
-            self.do_game(strategy, action_pause, debug)
+        for game in 0..games {
+            let tetris = Tetris::with_config(Config {
+                seed: self.config.seed.map(|seed| seed.wrapping_add(u64::from(game))),
+                ..self.config.clone()
+            });
+            self.do_game(strategy, tetris, action_pause, debug)
         }
     }
 
-    pub fn do_game(&self, strategy: &mut dyn Strategy, action_pause: u64, debug: bool) {
-        let mut tetris = Tetris::new();
+    pub fn do_game(&self, strategy: &mut dyn Strategy, mut tetris: Tetris, action_pause: u64, debug: bool) {
         let mut shape_count = 0;
         loop {
             shape_count = shape_count + 1;
+            while tetris.is_entering() {
+                tetris.tick(FRAME);
+            }
             let actions = strategy.choose_actions(&tetris);
             for action in actions {
                 if action_pause > 0 {
                     thread::sleep(time::Duration::from_millis(action_pause));
                 }
-                let result = tetris.input(&action);
+                let result = match tetris.input(&action) {
+                    result @ (ActionResult::NextShape(_) | ActionResult::GameOver(..)) => result,
+                    _ => tetris.tick(FRAME),
+                };
                 if debug {
                     Bot::draw(&tetris);
                 }
-                if result == ActionResult::NextShape {
+                if let ActionResult::NextShape(_) = result {
                     // this could happen if not all actions are required to reach the next shape
                     // eg if a down tick changes the sequence needed to complete the current shape
                     break;
                 }
-                if result == ActionResult::GameOver {
-                    println!("Game completed after {} shapes handled", shape_count);
+                if let ActionResult::GameOver(..) = result {
+                    println!("Game completed after {} shapes handled, {} lines cleared, level {}, score {}, \
+                              max combo {}, {} back-to-backs, {} perfect clears",
+                             shape_count, tetris.lines(), tetris.level(), tetris.score(),
+                             tetris.max_combo(), tetris.back_to_backs(), tetris.perfect_clears());
                     stdout().execute(cursor::Show).unwrap();
                     return;
                 }
@@ -50,19 +69,24 @@ impl Bot {
 
     fn draw(tetris: &Tetris) {
         let mut blocks_string = String::new();
-        blocks_string.push_str(" ---------- \n");
-        for y in 0..20 {
+        let ghost = tetris.ghost().unwrap_or([Block::new(-1, -1); 4]);
+        let ghost_colour = Bot::colour(Cell::of(tetris.current_shape()));
+        let edge = format!(" {} \n", "-".repeat(tetris.width() as usize));
+        blocks_string.push_str(&edge);
+        for y in 0..tetris.height() {
             blocks_string.push_str("|");
-            for x in 0..10 {
-                if tetris.block_at(x, y) {
-                    blocks_string.push_str("*");
-                } else {
-                    blocks_string.push_str(" ");
+            for x in 0..tetris.width() {
+                match (Bot::colour(tetris.cell_at(x, y)), ghost_colour) {
+                    (Some(colour), _) => blocks_string.push_str(&"*".with(colour).to_string()),
+                    (None, Some(colour)) if ghost.contains(&Block::new(x, y)) => {
+                        blocks_string.push_str(&"+".with(colour).dim().to_string())
+                    }
+                    _ => blocks_string.push_str(" "),
                 }
             }
             blocks_string.push_str("|\n");
         }
-        blocks_string.push_str(" ---------- \n");
+        blocks_string.push_str(&edge);
 
         let mut stdout = stdout();
         stdout.execute(cursor::Hide).unwrap();
@@ -72,4 +96,19 @@ impl Bot {
         stdout.write_all(blocks_string.as_bytes()).unwrap();
         stdout.flush().unwrap();
     }
-}
\ No newline at end of file
+
+    // the standard colours, as near as a terminal gets
+    fn colour(cell: Cell) -> Option<Color> {
+        match cell {
+            Cell::Empty => None,
+            Cell::I => Some(Color::Cyan),
+            Cell::O => Some(Color::Yellow),
+            Cell::T => Some(Color::Magenta),
+            Cell::S => Some(Color::Green),
+            Cell::Z => Some(Color::Red),
+            Cell::J => Some(Color::Blue),
+            Cell::L => Some(Color::DarkYellow),
+            Cell::Garbage => Some(Color::Grey),
+        }
+    }
+}
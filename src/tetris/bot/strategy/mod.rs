@@ -1,7 +1,8 @@
-use crate::tetris::{Action, ActionResult, Tetris};
-use crate::tetris::Action::{Drop, Left, Right, Rotate};
-use crate::tetris::ActionResult::{Invalid, NextShape};
+use crate::tetris::{Action, ActionResult, Snapshot, Tetris};
+use crate::tetris::Action::{Drop, Hold};
+use crate::tetris::ActionResult::CurrentShape;
 
+pub mod moves;
 pub mod qlearning;
 pub mod random;
 pub mod weighted;
@@ -18,54 +19,38 @@ pub trait Strategy {
     fn score(&self, outcome: &Tetris) -> f64;
 
     fn best_actions(&self, tetris: &Tetris) -> BestActions {
-        let mut best_actions = vec![Drop];
-        let mut best_actions_score = 0.0;
-        let mut best_action_result = NextShape;
-        for rotations in 0..4 {
-            {
-                let mut clone = tetris.clone();
-                let mut actions = vec![Rotate; rotations];
-                actions.push(Drop);
-                for i in 0..actions.len() {
-                    clone.input(&actions[i]);
-                }
-                let score = self.score(&clone);
-                if score > best_actions_score {
-                    best_actions = actions;
-                    best_actions_score = score;
-                }
-            }
-            for action in [Right, Left] {
-                let mut shift = 0;
-                loop {
-                    let mut clone = tetris.clone();
-                    let mut actions = vec![Rotate; rotations];
-                    actions.append(&mut vec![action; shift]);
-                    for i in 0..actions.len() {
-                        clone.input(&actions[i]);
-                    }
-                    let result = clone.input(&action);
-                    actions.push(action);
-                    if result == Invalid {
-                        break;
-                    } else {
-                        shift = shift + 1;
-                        clone.input(&Drop);
-                        actions.push(Drop);
-                        let score = self.score(&clone);
-                        if score > best_actions_score {
-                            best_actions = actions;
-                            best_actions_score = score;
-                            best_action_result = result;
-                        }
-                    }
-                }
-            }
+        let mut best = BestActions {
+            actions: vec![Drop],
+            score: 0.0,
+            result: CurrentShape,
+        };
+        let mut scratch = tetris.clone();
+        let start = scratch.snapshot();
+        search_placements(self, &mut scratch, &start, vec![], &mut best);
+        scratch.restore(&start);
+        if scratch.input(&Hold) == CurrentShape {
+            let held = scratch.snapshot();
+            search_placements(self, &mut scratch, &held, vec![Hold], &mut best);
+        }
+        best
+    }
+}
+
+fn search_placements<S: Strategy + ?Sized>(strategy: &S, tetris: &mut Tetris, start: &Snapshot, prefix: Vec<Action>, best: &mut BestActions) {
+    tetris.restore(start);
+    for generated in moves::generate(tetris) {
+        tetris.restore(start);
+        let mut result = CurrentShape;
+        for action in &generated.actions {
+            result = tetris.input(action);
         }
-        BestActions{
-            actions: best_actions,
-            score: best_actions_score,
-            result: best_action_result
+        let score = strategy.score(tetris);
+        if score > best.score {
+            let mut actions = prefix.clone();
+            actions.extend(generated.actions);
+            best.actions = actions;
+            best.score = score;
+            best.result = result;
         }
     }
 }
@@ -75,7 +60,7 @@ pub trait Strategy {
 mod tests {
     use crate::tetris::tests::tetris_with_only_j_shape;
     use crate::tetris::{Action, Block, Tetris};
-    use crate::tetris::Action::{Drop, Left, Right, Rotate};
+    use crate::tetris::Action::{Drop, Hold, Left, Right, Rotate, Rotate180, RotateCcw};
     use crate::tetris::bot::strategy::Strategy;
 
     fn has_blocks_at(tetris: &Tetris, blocks: [Block; 4]) -> bool {
@@ -155,6 +140,41 @@ mod tests {
         assert_eq!(expected_actions[..], actions.actions[..]);
     }
 
+    #[test]
+    fn should_half_rotate_and_drop_when_that_is_best() {
+        // given
+        let tetris = tetris_with_only_j_shape();
+
+        // and
+        struct TestStrategy {}
+        impl Strategy for TestStrategy {
+            fn choose_actions(&mut self, _: &Tetris) -> Vec<Action> {
+                todo!()
+            }
+            fn score(&self, outcome: &Tetris) -> f64 {
+                if has_blocks_at(outcome, [
+                    Block::new(3, 18),
+                    Block::new(4, 18),
+                    Block::new(5, 18),
+                    Block::new(5, 19)
+                ]) {
+                    1.0
+                } else {
+                    0.0
+                }
+            }
+        }
+
+
+        // when
+        let strategy = TestStrategy {};
+        let actions = strategy.best_actions(&tetris);
+
+        // then
+        let expected_actions = [Rotate180, Drop];
+        assert_eq!(expected_actions[..], actions.actions[..]);
+    }
+
     #[test]
     fn should_fully_rotate_and_drop_when_that_is_best() {
         // given
@@ -186,7 +206,7 @@ mod tests {
         let actions = strategy.best_actions(&tetris);
 
         // then
-        let expected_actions = vec![Rotate, Rotate, Rotate, Drop];
+        let expected_actions = vec![RotateCcw, Drop];
         assert_eq!(expected_actions[..], actions.actions[..]);
     }
 
@@ -399,4 +419,34 @@ mod tests {
         let expected_actions = vec![Rotate, Right, Right, Right, Right, Drop];
         assert_eq!(expected_actions[..], actions.actions[..]);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn should_hold_and_drop_when_that_is_best() {
+        // given
+        let tetris = Tetris::with_seed(1);
+
+        // and
+        struct TestStrategy {}
+        impl Strategy for TestStrategy {
+            fn choose_actions(&mut self, _: &Tetris) -> Vec<Action> {
+                todo!()
+            }
+            fn score(&self, outcome: &Tetris) -> f64 {
+                if outcome.held_shape().is_some() {
+                    1.0
+                } else {
+                    0.0
+                }
+            }
+        }
+
+
+        // when
+        let strategy = TestStrategy {};
+        let actions = strategy.best_actions(&tetris);
+
+        // then
+        let expected_actions = [Hold, Drop];
+        assert_eq!(expected_actions[..], actions.actions[..]);
+    }
+}
@@ -0,0 +1,66 @@
+use crate::tetris::{Action, Placement, Tetris};
+
+#[derive(Debug, Clone)]
+pub struct Move {
+    pub placement: Placement,
+    pub actions: Vec<Action>,
+}
+
+/// Every distinct place the current shape can lock, each with the fewest actions that get it
+/// there, from the engine's breadth first search over its positions and rotations. Soft dropping
+/// as well as hard dropping means it finds slides under overhangs and spins into slots too, and
+/// with a lock delay it keeps sliding and spinning shapes along the stack before dropping them
+pub fn generate(tetris: &Tetris) -> Vec<Move> {
+    tetris.legal_moves().into_iter()
+        .map(|(placement, actions)| Move { placement, actions })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::tetris::{ActionResult, Block, Cell};
+    use crate::tetris::Action::{Down, Drop, Left};
+    use crate::tetris::tests::tetris_with_only_j_shape;
+    use super::*;
+
+    #[test]
+    fn should_generate_every_lock_on_an_empty_board_by_hard_dropping() {
+        // given
+        let tetris = tetris_with_only_j_shape();
+
+        // when
+        let moves = generate(&tetris);
+
+        // then
+        assert_eq!(8 + 9 + 8 + 9, moves.len());
+        assert!(moves.iter().all(|generated| generated.actions.last() == Some(&Drop)));
+        assert!(moves.iter().any(|generated| generated.actions == vec![Drop]));
+    }
+
+    #[test]
+    fn should_generate_actions_that_slide_under_an_overhang() {
+        // given
+        let mut tetris = tetris_with_only_j_shape();
+        for x in 0..6 {
+            tetris.dead_blocks.fill(&[Block::new(x, 16); 4], Cell::Garbage);
+        }
+
+        // when
+        let moves = generate(&tetris);
+
+        // then
+        let tuck = moves.iter()
+            .find(|generated| tetris.placement_blocks(&generated.placement).contains(&Block::new(0, 18)))
+            .unwrap();
+        assert!(tuck.actions.contains(&Left));
+        assert!(tuck.actions.contains(&Down));
+        let mut result = ActionResult::Invalid;
+        for action in &tuck.actions {
+            result = tetris.input(action);
+        }
+        match result {
+            ActionResult::NextShape(lock) => assert!(lock.blocks.contains(&Block::new(0, 18))),
+            _ => panic!("{:?}", result),
+        }
+    }
+}
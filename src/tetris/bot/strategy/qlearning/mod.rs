@@ -2,7 +2,7 @@
 // use tch::nn::{Module, Optimizer, OptimizerConfig, Sequential, VarStore};
 use crate::tetris::bot::strategy::Strategy;
 use crate::tetris::{Action, Tetris};
-use crate::tetris::ActionResult::GameOver;
+use crate::tetris::ActionResult;
 use crate::tetris::bot::analysis::analyse;
 use crate::tetris::bot::strategy::random::Random;
 
@@ -113,7 +113,7 @@ impl QLearning {
 impl Strategy for QLearning {
     fn choose_actions(&mut self, tetris: &Tetris) -> Vec<Action> {
         let best_actions = self.best_actions(tetris);
-        if best_actions.result == GameOver {
+        if let ActionResult::GameOver(..) = best_actions.result {
             self.train(tetris, GAME_OVER_PENALTY);
         } else {
             self.train(tetris, best_actions.score * DISCOUNT_RATE);
@@ -1,29 +1,95 @@
 use std::sync::mpsc;
-use std::{process, thread, time};
+use std::sync::mpsc::RecvTimeoutError;
+use std::{thread, time};
+use std::time::Instant;
+use clap::Parser;
+use fltk::app;
 use tetris_rust::tetris::gui::Gui;
-use tetris_rust::tetris::{ActionResult, Tetris};
+use tetris_rust::tetris::gui::auto_shift::Handling;
+use tetris_rust::tetris::gui::key_bindings::KeyBindings;
+use tetris_rust::tetris::gui::session::Session;
+use tetris_rust::tetris::Config;
+use tetris_rust::tetris::randomizer;
+use tetris_rust::tetris::rotation::RotationSystem;
+use tetris_rust::tetris::scoring::ScoringSystem;
+use tetris_rust::tetris::timing::{SpeedCurve, Timing};
 
+#[derive(Parser)]
+struct GameArgs {
+    /// The randomizer name: bag, random, nes or tgm
+    #[arg(short, long, default_value = "bag")]
+    randomizer: String,
+    /// Seed for the shape sequence
+    #[arg(long)]
+    seed: Option<u64>,
+    /// The scoring system name: guideline or nes
+    #[arg(long, default_value = "guideline")]
+    scoring: String,
+    /// The rotation system name: srs or classic
+    #[arg(long, default_value = "srs")]
+    rotation: String,
+    /// How many blocks wide the board is
+    #[arg(long, default_value_t = 10, value_parser = clap::value_parser!(u8).range(4..=64))]
+    width: u8,
+    /// How many blocks high the board is
+    #[arg(long, default_value_t = 20, value_parser = clap::value_parser!(u8).range(4..=64))]
+    height: u8,
+    /// How many hidden rows there are above the board for shapes to spawn into
+    #[arg(long, default_value_t = 20, value_parser = clap::value_parser!(u8).range(..=40))]
+    vanish_rows: u8,
+    /// The speed curve name: guideline, nes or tgm
+    #[arg(long, default_value = "guideline")]
+    speed_curve: String,
+    /// Delayed auto shift, how many milliseconds Left or Right is held before it repeats
+    #[arg(long, default_value_t = 167)]
+    das: u64,
+    /// Auto repeat rate, how many milliseconds between repeats, where 0 moves straight to the wall
+    #[arg(long, default_value_t = 33)]
+    arr: u64,
+}
 
 fn main() {
-    let (action_sender, action_receiver) = mpsc::channel();
-    let (blocks_sender, blocks_receiver) = mpsc::channel();
+    let game_args = GameArgs::parse();
+    let config = Config {
+        randomizer: randomizer::from_name(&game_args.randomizer).expect("not a known randomizer"),
+        seed: game_args.seed,
+        scoring: ScoringSystem::from_name(&game_args.scoring).expect("not a known scoring system"),
+        rotation_system: RotationSystem::from_name(&game_args.rotation).expect("not a known rotation system"),
+        width: game_args.width,
+        height: game_args.height,
+        vanish_rows: game_args.vanish_rows,
+        timing: Some(Timing {
+            speed_curve: SpeedCurve::from_name(&game_args.speed_curve).expect("not a known speed curve"),
+            ..Timing::default()
+        }),
+        ..Config::default()
+    };
+    let (command_sender, command_receiver) = mpsc::channel();
+    let (render_sender, render_receiver) = mpsc::channel();
+    let (width, height) = (config.width as i8, config.height as i8);
+    let handling = Handling {
+        das: time::Duration::from_millis(game_args.das),
+        arr: time::Duration::from_millis(game_args.arr),
+    };
+    let key_bindings = KeyBindings::load().unwrap_or_else(|error| {
+        eprintln!("Couldn't load the key bindings, so using the defaults: {}", error);
+        KeyBindings::default()
+    });
     thread::spawn(move || {
-        let mut tetris = Tetris::new();
+        let mut session = Session::new(config);
+        let mut last_tick = Instant::now();
         loop {
-            let result = tetris.input(&action_receiver.recv().unwrap());
-
-            let mut blocks = [[false; 20]; 10];
-            for x in 0i8..10i8 {
-                for y in 0i8..20i8 {
-                    blocks[usize::from(x.unsigned_abs())][usize::from(y.unsigned_abs())] = tetris.block_at(x, y);
-                }
-            }
-            blocks_sender.send(blocks).unwrap();
-            if result == ActionResult::GameOver {
-                thread::sleep(time::Duration::from_millis(500));
-                process::exit(0);
+            match command_receiver.recv_timeout(time::Duration::from_millis(10)) {
+                Ok(command) => session.command(command),
+                Err(RecvTimeoutError::Timeout) => {}
+                Err(RecvTimeoutError::Disconnected) => return,
             }
+            session.tick(last_tick.elapsed());
+            last_tick = Instant::now();
+            render_sender.send(session.render_state()).unwrap();
+            // wake the GUI to draw it, as it otherwise only wakes for its own events
+            app::awake();
         }
     });
-    Gui::launch(action_sender, blocks_receiver, false);
+    Gui::launch(width, height, handling, key_bindings, command_sender, render_receiver, false);
 }
\ No newline at end of file
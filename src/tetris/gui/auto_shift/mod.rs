@@ -0,0 +1,207 @@
+use std::time::Duration;
+use crate::tetris::Action;
+
+/// How held movement keys repeat, in place of the OS key repeat which differs by machine
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub struct Handling {
+    /// Delayed auto shift, how long a key is held before it starts repeating
+    pub das: Duration,
+    /// Auto repeat rate, how long between repeats, where none moves straight to the wall
+    pub arr: Duration,
+}
+
+impl Default for Handling {
+    fn default() -> Self {
+        Handling {
+            das: Duration::from_millis(167),
+            arr: Duration::from_millis(33),
+        }
+    }
+}
+
+pub struct AutoShift {
+    handling: Handling,
+    wall_moves: usize,
+    /// The keys held down, with the one repeating last
+    held: Vec<Action>,
+    held_for: Duration,
+    /// Whether the shape has already been moved to the wall with no repeat rate
+    slid: bool,
+}
+
+impl AutoShift {
+    /// The wall moves are how many moves are enough to reach the wall from anywhere on the board
+    pub fn new(handling: Handling, wall_moves: usize) -> AutoShift {
+        AutoShift {
+            handling,
+            wall_moves,
+            held: vec![],
+            held_for: Duration::ZERO,
+            slid: false,
+        }
+    }
+
+    /// The moves for a key going down, which is one for a new key and none for the OS repeating
+    /// a key that's still held
+    pub fn press(&mut self, action: Action) -> Vec<Action> {
+        if self.held.last() == Some(&action) {
+            return vec![];
+        }
+        self.held.retain(|held| *held != action);
+        self.held.push(action);
+        self.restart();
+        vec![action]
+    }
+
+    /// Lets go of the key, going back to repeating the other if it's still held, after the delay
+    pub fn release(&mut self, action: Action) {
+        let was_repeating = self.held.last() == Some(&action);
+        self.held.retain(|held| *held != action);
+        if was_repeating {
+            self.restart();
+        }
+    }
+
+    /// Lets a key still held with no repeat rate move the next shape to the wall too
+    pub fn new_shape(&mut self) {
+        self.slid = false;
+    }
+
+    /// The moves from the held key repeating over the time since the last tick
+    pub fn tick(&mut self, elapsed: Duration) -> Vec<Action> {
+        let action = match self.held.last() {
+            Some(action) => *action,
+            None => return vec![],
+        };
+        if self.handling.arr.is_zero() {
+            self.held_for += elapsed;
+            if self.held_for < self.handling.das || self.slid {
+                return vec![];
+            }
+            self.slid = true;
+            return vec![action; self.wall_moves];
+        }
+        let before = self.repeats(self.held_for);
+        self.held_for += elapsed;
+        vec![action; self.repeats(self.held_for) - before]
+    }
+
+    fn restart(&mut self) {
+        self.held_for = Duration::ZERO;
+        self.slid = false;
+    }
+
+    // How many repeats there have been after holding a key for so long, with the first as soon as
+    // the auto shift charges and the rest at the repeat rate
+    fn repeats(&self, held_for: Duration) -> usize {
+        if held_for < self.handling.das {
+            return 0;
+        }
+        ((held_for - self.handling.das).as_nanos() / self.handling.arr.as_nanos()) as usize + 1
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::tetris::Action::{Left, Right};
+    use super::*;
+
+    fn auto_shift(das: u64, arr: u64) -> AutoShift {
+        AutoShift::new(Handling { das: Duration::from_millis(das), arr: Duration::from_millis(arr) }, 10)
+    }
+
+    #[test]
+    fn should_move_once_when_pressed_and_ignore_os_repeats() {
+        // given
+        let mut auto_shift = auto_shift(100, 20);
+
+        // when
+        let pressed = auto_shift.press(Left);
+        let repeated = auto_shift.press(Left);
+
+        // then
+        assert_eq!(vec![Left], pressed);
+        assert_eq!(Vec::<Action>::new(), repeated);
+    }
+
+    #[test]
+    fn should_repeat_at_the_rate_once_the_delay_has_passed() {
+        // given
+        let mut auto_shift = auto_shift(100, 20);
+        auto_shift.press(Right);
+
+        // when
+        let before_delay = auto_shift.tick(Duration::from_millis(99));
+        let at_delay = auto_shift.tick(Duration::from_millis(1));
+        let after_delay = auto_shift.tick(Duration::from_millis(45));
+
+        // then
+        assert!(before_delay.is_empty());
+        assert_eq!(vec![Right], at_delay);
+        assert_eq!(vec![Right, Right], after_delay);
+    }
+
+    #[test]
+    fn should_move_to_the_wall_with_no_repeat_rate() {
+        // given
+        let mut auto_shift = auto_shift(100, 0);
+        auto_shift.press(Left);
+
+        // when
+        let moves = auto_shift.tick(Duration::from_millis(100));
+
+        // then
+        assert_eq!(vec![Left; 10], moves);
+    }
+
+    #[test]
+    fn should_move_to_the_wall_once_a_shape_with_no_repeat_rate() {
+        // given
+        let mut auto_shift = auto_shift(100, 0);
+        auto_shift.press(Left);
+        auto_shift.tick(Duration::from_millis(100));
+
+        // when
+        let same_shape = auto_shift.tick(Duration::from_millis(10));
+        auto_shift.new_shape();
+        let new_shape = auto_shift.tick(Duration::from_millis(10));
+
+        // then
+        assert!(same_shape.is_empty());
+        assert_eq!(vec![Left; 10], new_shape);
+    }
+
+    #[test]
+    fn should_go_back_to_the_key_still_held_after_the_delay() {
+        // given
+        let mut auto_shift = auto_shift(100, 20);
+        auto_shift.press(Left);
+        auto_shift.press(Right);
+        auto_shift.tick(Duration::from_millis(150));
+
+        // when
+        auto_shift.release(Right);
+        let before_delay = auto_shift.tick(Duration::from_millis(99));
+        let at_delay = auto_shift.tick(Duration::from_millis(1));
+
+        // then
+        assert!(before_delay.is_empty());
+        assert_eq!(vec![Left], at_delay);
+    }
+
+    #[test]
+    fn should_stop_repeating_when_released() {
+        // given
+        let mut auto_shift = auto_shift(100, 20);
+        auto_shift.press(Left);
+        auto_shift.press(Right);
+        auto_shift.release(Left);
+
+        // when
+        auto_shift.release(Right);
+        let moves = auto_shift.tick(Duration::from_millis(200));
+
+        // then
+        assert!(moves.is_empty());
+    }
+}
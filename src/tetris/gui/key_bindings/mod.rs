@@ -0,0 +1,437 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::{fs, io};
+use fltk::enums::Key;
+use serde::Deserialize;
+use crate::tetris::Action;
+use crate::tetris::gui::session::Command;
+
+/// Opens the remapping screen from the title or pause screens, and keeps a control's keys while
+/// remapping. It has no name, so it can't be bound to anything else
+pub const REMAP_KEY: Key = Key::F1;
+
+// the keys that aren't just the character they type
+const KEY_NAMES: [(&str, Key); 22] = [
+    ("Left", Key::Left),
+    ("Right", Key::Right),
+    ("Up", Key::Up),
+    ("Down", Key::Down),
+    ("Space", Key::from_char(' ')),
+    ("Enter", Key::Enter),
+    ("KPEnter", Key::KPEnter),
+    ("Escape", Key::Escape),
+    ("Tab", Key::Tab),
+    ("BackSpace", Key::BackSpace),
+    ("ShiftL", Key::ShiftL),
+    ("ShiftR", Key::ShiftR),
+    ("ControlL", Key::ControlL),
+    ("ControlR", Key::ControlR),
+    ("AltL", Key::AltL),
+    ("AltR", Key::AltR),
+    ("Home", Key::Home),
+    ("End", Key::End),
+    ("PageUp", Key::PageUp),
+    ("PageDown", Key::PageDown),
+    ("Insert", Key::Insert),
+    ("Delete", Key::Delete),
+];
+
+/// The name of a key in the config file, or nothing if it can't be bound
+pub fn key_name(key: Key) -> Option<String> {
+    match KEY_NAMES.iter().find(|(_, named_key)| *named_key == key) {
+        Some((name, _)) => Some(name.to_string()),
+        None if (0x21..0x7f).contains(&key.bits()) => {
+            Some((key.bits() as u8 as char).to_string())
+        }
+        None => None,
+    }
+}
+
+/// The key with the name, where a single character is the key that types it
+pub fn key_from_name(name: &str) -> Option<Key> {
+    let mut chars = name.chars();
+    match (chars.next(), chars.next()) {
+        (Some(char), None) if char.is_ascii_graphic() => Some(Key::from_char(char.to_ascii_lowercase())),
+        _ => KEY_NAMES.iter().find(|(key_name, _)| *key_name == name).map(|(_, key)| *key),
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Hash, Copy, Clone)]
+pub enum Control {
+    Left,
+    Right,
+    SoftDrop,
+    HardDrop,
+    Rotate,
+    RotateCcw,
+    Rotate180,
+    Hold,
+    Pause,
+    Restart,
+}
+
+impl Control {
+    pub const ALL: [Control; 10] = [
+        Control::Left,
+        Control::Right,
+        Control::SoftDrop,
+        Control::HardDrop,
+        Control::Rotate,
+        Control::RotateCcw,
+        Control::Rotate180,
+        Control::Hold,
+        Control::Pause,
+        Control::Restart,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Control::Left => "left",
+            Control::Right => "right",
+            Control::SoftDrop => "soft_drop",
+            Control::HardDrop => "hard_drop",
+            Control::Rotate => "rotate",
+            Control::RotateCcw => "rotate_ccw",
+            Control::Rotate180 => "rotate_180",
+            Control::Hold => "hold",
+            Control::Pause => "pause",
+            Control::Restart => "restart",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Control> {
+        Control::ALL.iter().find(|control| control.name() == name).copied()
+    }
+
+    /// What the game is sent when the key goes down
+    pub fn command(&self) -> Command {
+        match self {
+            Control::Left => Command::Action(Action::Left),
+            Control::Right => Command::Action(Action::Right),
+            Control::SoftDrop => Command::Action(Action::SoftDrop(true)),
+            Control::HardDrop => Command::Action(Action::Drop),
+            Control::Rotate => Command::Action(Action::Rotate),
+            Control::RotateCcw => Command::Action(Action::RotateCcw),
+            Control::Rotate180 => Command::Action(Action::Rotate180),
+            Control::Hold => Command::Action(Action::Hold),
+            Control::Pause => Command::Pause,
+            Control::Restart => Command::NewGame,
+        }
+    }
+}
+
+/// Which keys do what, where each key does at most one thing
+#[derive(Debug, PartialEq, Clone)]
+pub struct KeyBindings {
+    keys: HashMap<Control, Vec<Key>>,
+}
+
+impl Default for KeyBindings {
+    fn default() -> Self {
+        KeyBindings {
+            keys: HashMap::from([
+                (Control::Left, vec![Key::Left]),
+                (Control::Right, vec![Key::Right]),
+                (Control::SoftDrop, vec![Key::Down]),
+                (Control::HardDrop, vec![Key::from_char(' ')]),
+                (Control::Rotate, vec![Key::Up]),
+                (Control::RotateCcw, vec![Key::from_char('z')]),
+                (Control::Rotate180, vec![Key::from_char('a')]),
+                (Control::Hold, vec![Key::ShiftL, Key::ShiftR]),
+                (Control::Pause, vec![Key::from_char('p'), Key::Escape]),
+                (Control::Restart, vec![Key::from_char('r'), Key::Enter, Key::KPEnter]),
+            ]),
+        }
+    }
+}
+
+impl KeyBindings {
+    /// Loads the bindings from keys.toml in the user's config directory, keeping the defaults
+    /// for anything the file doesn't bind or if there's no file
+    pub fn load() -> Result<KeyBindings, String> {
+        let path = match KeyBindings::path() {
+            Some(path) => path,
+            None => return Ok(KeyBindings::default()),
+        };
+        match fs::read_to_string(&path) {
+            Ok(text) => KeyBindings::parse(&text).map_err(|error| format!("{}: {}", path.display(), error)),
+            Err(error) if error.kind() == io::ErrorKind::NotFound => Ok(KeyBindings::default()),
+            Err(error) => Err(format!("{}: {}", path.display(), error)),
+        }
+    }
+
+    pub fn save(&self) -> Result<(), String> {
+        let path = KeyBindings::path().ok_or("no config directory to save the key bindings in")?;
+        if let Some(directory) = path.parent() {
+            fs::create_dir_all(directory).map_err(|error| format!("{}: {}", directory.display(), error))?;
+        }
+        fs::write(&path, self.to_toml()).map_err(|error| format!("{}: {}", path.display(), error))
+    }
+
+    // the platform's config directory, eg ~/.config on Linux or ~/Library/Application Support on macOS
+    fn path() -> Option<PathBuf> {
+        Some(dirs::config_dir()?.join("tetris-rust").join("keys.toml"))
+    }
+
+    /// Reads a TOML table binding each control to a key name or a list of them, eg
+    /// `hold = ["ShiftL", "c"]`, on top of the defaults
+    pub fn parse(text: &str) -> Result<KeyBindings, String> {
+        let file: HashMap<String, KeyNames> = toml::from_str(text).map_err(|error| error.message().to_string())?;
+        let mut bound: HashMap<Control, Vec<Key>> = HashMap::new();
+        for (name, key_names) in file {
+            let control = Control::from_name(&name).ok_or_else(|| format!("not a known control: {}", name))?;
+            let key_names = match key_names {
+                KeyNames::One(key_name) => vec![key_name],
+                KeyNames::Many(key_names) => key_names,
+            };
+            let keys = key_names.iter()
+                .map(|key_name| key_from_name(key_name).ok_or_else(|| format!("not a known key: {}", key_name)))
+                .collect::<Result<Vec<Key>, String>>()?;
+            bound.insert(control, keys);
+        }
+        let mut key_bindings = KeyBindings::default();
+        for control in Control::ALL {
+            if let Some(keys) = bound.remove(&control) {
+                if let Some(key) = keys.iter().find(|key| bound.values().any(|other_keys| other_keys.contains(key))) {
+                    return Err(format!("bound to more than one control: {}", key_name(*key).unwrap_or_default()));
+                }
+                key_bindings.bind_all(control, keys);
+            }
+        }
+        Ok(key_bindings)
+    }
+
+    pub fn to_toml(&self) -> String {
+        let mut toml = String::from("# Each control is bound to a key name or a list of them\n");
+        for control in Control::ALL {
+            let key_names: Vec<String> = self.keys(control).iter().filter_map(|key| key_name(*key)).collect();
+            toml.push_str(&format!("{} = {}\n", control.name(), toml::Value::from(key_names)));
+        }
+        toml
+    }
+
+    /// Tells the player which keys to press to do something with the control
+    pub fn prompt(&self, control: Control, what: &str) -> String {
+        let key_names: Vec<String> = self.keys(control).iter().filter_map(|key| key_name(*key)).collect();
+        if key_names.is_empty() {
+            format!("Nothing to {} with, F1 to bind it", what)
+        } else {
+            format!("{} to {}", key_names.join(" or "), what)
+        }
+    }
+
+    pub fn control(&self, key: Key) -> Option<Control> {
+        Control::ALL.iter().find(|control| self.keys(**control).contains(&key)).copied()
+    }
+
+    pub fn keys(&self, control: Control) -> &[Key] {
+        self.keys.get(&control).map_or(&[], |keys| keys)
+    }
+
+    /// Binds the control to just the key, taking the key from whatever else it did
+    pub fn bind(&mut self, control: Control, key: Key) {
+        self.bind_all(control, vec![key]);
+    }
+
+    fn bind_all(&mut self, control: Control, keys: Vec<Key>) {
+        for other_keys in self.keys.values_mut() {
+            other_keys.retain(|key| !keys.contains(key));
+        }
+        self.keys.insert(control, keys);
+    }
+}
+
+// a control in the file is bound to a key name or a list of them
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum KeyNames {
+    One(String),
+    Many(Vec<String>),
+}
+
+/// Steps through every control in turn, binding each to the next key pressed
+#[derive(Default)]
+pub struct Remapping {
+    next: usize,
+}
+
+impl Remapping {
+    pub fn new() -> Remapping {
+        Remapping { next: 0 }
+    }
+
+    /// The control waiting for a key, or nothing once they've all had one
+    pub fn control(&self) -> Option<Control> {
+        Control::ALL.get(self.next).copied()
+    }
+
+    /// Binds the waiting control to the key, where the remap key keeps the keys it has and keys
+    /// without a name are ignored
+    pub fn press(&mut self, key_bindings: &mut KeyBindings, key: Key) {
+        let control = match self.control() {
+            Some(control) => control,
+            None => return,
+        };
+        if key == REMAP_KEY {
+            self.next += 1;
+        } else if key_name(key).is_some() {
+            key_bindings.bind(control, key);
+            self.next += 1;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_bind_every_control_by_default() {
+        // given
+        let key_bindings = KeyBindings::default();
+
+        // when / then
+        for control in Control::ALL {
+            assert!(!key_bindings.keys(control).is_empty());
+        }
+        assert_eq!(Some(Control::Hold), key_bindings.control(Key::ShiftR));
+        assert_eq!(Some(Control::HardDrop), key_bindings.control(Key::from_char(' ')));
+        assert_eq!(None, key_bindings.control(Key::from_char('q')));
+    }
+
+    #[test]
+    fn should_name_keys() {
+        // when / then
+        assert_eq!(Some("ShiftL".to_string()), key_name(Key::ShiftL));
+        assert_eq!(Some("x".to_string()), key_name(Key::from_char('x')));
+        assert_eq!(None, key_name(REMAP_KEY));
+        assert_eq!(Some(Key::from_char('x')), key_from_name("X"));
+        assert_eq!(Some(Key::from_char(' ')), key_from_name("Space"));
+        assert_eq!(None, key_from_name("Nope"));
+    }
+
+    #[test]
+    fn should_parse_bindings_on_top_of_the_defaults() {
+        // given
+        let text = "# my keys\nhold = [\n  'c',\n  \"ShiftL\", # both\n]\n\nrotate = \"x\"\nrotate_ccw = \"#\"\nrotate_180 = \"\\u0022\"\n";
+
+        // when
+        let key_bindings = KeyBindings::parse(text).unwrap();
+
+        // then
+        assert_eq!(&[Key::from_char('c'), Key::ShiftL], key_bindings.keys(Control::Hold));
+        assert_eq!(&[Key::from_char('x')], key_bindings.keys(Control::Rotate));
+        assert_eq!(&[Key::from_char('#')], key_bindings.keys(Control::RotateCcw));
+        assert_eq!(&[Key::from_char('"')], key_bindings.keys(Control::Rotate180));
+        assert_eq!(&[Key::Left], key_bindings.keys(Control::Left));
+    }
+
+    #[test]
+    fn should_not_parse_unknown_controls_or_keys() {
+        // when / then
+        assert_eq!(Err("not a known control: jump".to_string()), KeyBindings::parse("\njump = \"Up\""));
+        assert_eq!(Err("not a known key: Nope".to_string()), KeyBindings::parse("hold = [\"c\", \"Nope\"]"));
+        assert!(KeyBindings::parse("hold = \"c\" \"d\"").is_err());
+        assert!(KeyBindings::parse("[hold]\nkey = \"c\"").is_err());
+        assert!(KeyBindings::parse("hold").is_err());
+    }
+
+    #[test]
+    fn should_not_parse_a_key_bound_to_two_controls() {
+        // when
+        let result = KeyBindings::parse("hold = \"c\"\nrotate = [\"x\", \"c\"]");
+
+        // then
+        assert_eq!(Err("bound to more than one control: c".to_string()), result);
+    }
+
+    #[test]
+    fn should_take_a_key_from_its_old_control() {
+        // given
+        let mut key_bindings = KeyBindings::default();
+
+        // when
+        key_bindings.bind(Control::Hold, Key::Up);
+
+        // then
+        assert_eq!(Some(Control::Hold), key_bindings.control(Key::Up));
+        assert!(key_bindings.keys(Control::Rotate).is_empty());
+    }
+
+    #[test]
+    fn should_read_back_what_it_writes() {
+        // given
+        let mut key_bindings = KeyBindings::default();
+        key_bindings.bind(Control::Rotate180, Key::from_char('s'));
+        key_bindings.bind(Control::RotateCcw, Key::from_char('"'));
+
+        // when
+        let read_back = KeyBindings::parse(&key_bindings.to_toml()).unwrap();
+
+        // then
+        assert_eq!(key_bindings, read_back);
+    }
+
+    #[test]
+    fn should_prompt_with_the_keys_bound() {
+        // given
+        let mut key_bindings = KeyBindings::default();
+        key_bindings.bind(Control::Restart, Key::from_char('n'));
+
+        // when
+        let restart = key_bindings.prompt(Control::Restart, "start");
+        key_bindings.bind(Control::Hold, Key::from_char('p'));
+        key_bindings.bind(Control::Hold, Key::Escape);
+        let pause = key_bindings.prompt(Control::Pause, "resume");
+
+        // then
+        assert_eq!("n to start", restart);
+        assert_eq!("Nothing to resume with, F1 to bind it", pause);
+    }
+
+    #[test]
+    fn should_remap_each_control_in_turn() {
+        // given
+        let mut key_bindings = KeyBindings::default();
+        let mut remapping = Remapping::new();
+
+        // when
+        remapping.press(&mut key_bindings, Key::from_char('j'));
+        remapping.press(&mut key_bindings, REMAP_KEY);
+        remapping.press(&mut key_bindings, Key::F2);
+        remapping.press(&mut key_bindings, Key::Escape);
+
+        // then
+        assert_eq!(&[Key::from_char('j')], key_bindings.keys(Control::Left));
+        assert_eq!(&[Key::Right], key_bindings.keys(Control::Right));
+        assert_eq!(&[Key::Escape], key_bindings.keys(Control::SoftDrop));
+        assert_eq!(&[Key::from_char('p')], key_bindings.keys(Control::Pause));
+        assert_eq!(Some(Control::HardDrop), remapping.control());
+    }
+
+    #[test]
+    fn should_finish_remapping_after_the_last_control() {
+        // given
+        let mut key_bindings = KeyBindings::default();
+        let mut remapping = Remapping::new();
+
+        // when
+        for _ in Control::ALL {
+            remapping.press(&mut key_bindings, REMAP_KEY);
+        }
+
+        // then
+        assert_eq!(None, remapping.control());
+        assert_eq!(KeyBindings::default(), key_bindings);
+    }
+
+    #[test]
+    fn should_send_the_engine_action_for_each_control() {
+        // when / then
+        assert_eq!(Command::Action(Action::Hold), Control::Hold.command());
+        assert_eq!(Command::Action(Action::Rotate180), Control::Rotate180.command());
+        assert_eq!(Command::Action(Action::SoftDrop(true)), Control::SoftDrop.command());
+        assert_eq!(Command::Action(Action::Drop), Control::HardDrop.command());
+        assert_eq!(Command::Pause, Control::Pause.command());
+    }
+}
@@ -1,60 +1,172 @@
 use std::cell::RefCell;
+use std::collections::HashSet;
 use std::rc::Rc;
 use std::sync::mpsc::{Receiver, Sender};
+use std::time::{Duration, Instant};
 use fltk::{app, frame::Frame, prelude::*, window::Window};
 use fltk::app::{add_timeout3, App, event_key};
-use fltk::draw::{draw_rect_fill, draw_rect_with_color, Offscreen};
-use fltk::enums::{Color, Event, Key};
-use crate::tetris::{Action};
-use crate::tetris::Action::Down;
+use fltk::draw::{draw_rect_fill, draw_rect_with_color, draw_text, set_draw_color, set_font, Offscreen};
+use fltk::enums::{Color, Event, Font};
+use crate::tetris::{Action, Block, Cell, Shape, ShapeKind, Tetris};
+use crate::tetris::gui::auto_shift::{AutoShift, Handling};
+use crate::tetris::gui::key_bindings::{key_name, Control, KeyBindings, Remapping, REMAP_KEY};
+use crate::tetris::gui::session::{Command, Screen};
+
+pub mod auto_shift;
+pub mod key_bindings;
+pub mod session;
+
+// the hold and next shapes are drawn either side of the board
+const PANEL_WIDTH: i32 = 120;
+const PREVIEW_BLOCK_SIZE: i32 = 16;
+
+/// What the game sends the GUI to draw
+pub struct RenderState {
+    pub screen: Screen,
+    /// The visible locked cells by column then row
+    pub cells: Vec<Vec<Cell>>,
+    /// Where the current shape is and what it is, or nothing while waiting for it to appear
+    pub active: Option<(Cell, [Block; 4])>,
+    /// Where the current shape would land and what it is
+    pub ghost: Option<(Cell, [Block; 4])>,
+    pub next: Vec<ShapeKind>,
+    pub hold: Option<ShapeKind>,
+    /// How many shapes have come into play, to tell when the current one changes
+    pub shapes: u32,
+    pub score: u32,
+    pub level: u32,
+    pub lines: u32,
+    /// How long the game has been played
+    pub time: Duration,
+}
+
+impl RenderState {
+    pub fn of(tetris: &Tetris, screen: Screen, time: Duration) -> RenderState {
+        let cell = Cell::of(tetris.current_shape());
+        RenderState {
+            screen,
+            cells: (0..tetris.width())
+                .map(|x| (0..tetris.height()).map(|y| tetris.locked_cell_at(x, y)).collect())
+                .collect(),
+            active: tetris.active_blocks().map(|blocks| (cell, blocks)),
+            ghost: tetris.ghost().map(|blocks| (cell, blocks)),
+            next: tetris.next_shapes(),
+            hold: tetris.held_shape(),
+            shapes: tetris.shapes(),
+            score: tetris.score(),
+            level: tetris.level(),
+            lines: tetris.lines(),
+            time,
+        }
+    }
+}
 
 pub struct Gui {}
 
 impl Gui {
-    pub fn launch(action_sender: Sender<Action>, blocks_receiver: Receiver<[[bool; 20]; 10]>, headless: bool) {
+    pub fn launch(width: i8, height: i8, handling: Handling, key_bindings: KeyBindings, command_sender: Sender<Command>, render_receiver: Receiver<RenderState>, headless: bool) {
         let app = App::default();
+        let board_width = i32::from(width) * 20;
+        let pixel_width = board_width + 2 * PANEL_WIDTH;
+        // tall enough for the stats and five next shapes on short boards
+        let pixel_height = (i32::from(height) * 20).max(290);
         let mut window = Window::default()
-            .with_size(200, 400)
+            .with_size(pixel_width, pixel_height)
             .center_screen()
             .with_label("Tetris");
         let mut frame = Frame::default()
-            .with_size(200, 400)
+            .with_size(pixel_width, pixel_height)
             .center_of(&window);
         frame.set_color(Color::White);
         window.make_resizable(false);
         window.end();
         window.show();
+        // Escape would close the window, but it's bound like any other key, so only closing it quits
+        window.set_callback(|_| {
+            if app::event() == Event::Close {
+                app::quit();
+            }
+        });
 
-        let timer_sender = action_sender.clone();
-        let key_event_sender = action_sender.clone();
+        let key_event_sender = command_sender.clone();
+        let timer_sender = command_sender;
+        let auto_shift = Rc::from(RefCell::from(AutoShift::new(handling, width as usize)));
 
         /* Event handling */
-        let callback = move |handle| {
-            timer_sender.send(Down).unwrap();
-            app::repeat_timeout3(0.3, handle);
+        let mut last_tick = Instant::now();
+        let callback = {
+            let auto_shift = auto_shift.clone();
+            move |handle| {
+                for action in auto_shift.borrow_mut().tick(last_tick.elapsed()) {
+                    timer_sender.send(Command::Action(action)).unwrap();
+                }
+                last_tick = Instant::now();
+                app::repeat_timeout3(0.01, handle);
+            }
         };
-        add_timeout3(0.3, callback);
-
+        add_timeout3(0.01, callback);
 
+        let key_bindings = Rc::from(RefCell::from(key_bindings));
+        let remapping: Rc<RefCell<Option<Remapping>>> = Rc::from(RefCell::from(None));
+        let screen = Rc::from(RefCell::from(Screen::Title));
         if !headless {
+            let auto_shift = auto_shift.clone();
+            let mut held_keys = HashSet::new();
+            let key_bindings = key_bindings.clone();
+            let remapping = remapping.clone();
+            let screen = screen.clone();
             window.handle(move |_, event| {
                 match event {
                     Event::KeyDown => {
-                        match event_key() {
-                            Key::Up => {
-                                key_event_sender.send(Action::Rotate).unwrap();
+                        let key = event_key();
+                        // the OS repeats held keys, which would rotate or drop again and again
+                        if !held_keys.insert(key) {
+                            return true;
+                        }
+                        let mut remapping = remapping.borrow_mut();
+                        if let Some(remap) = remapping.as_mut() {
+                            remap.press(&mut key_bindings.borrow_mut(), key);
+                            if remap.control().is_none() {
+                                *remapping = None;
+                                if let Err(error) = key_bindings.borrow().save() {
+                                    eprintln!("Couldn't save the key bindings: {}", error);
+                                }
+                            }
+                            return true;
+                        }
+                        if key == REMAP_KEY && matches!(*screen.borrow(), Screen::Title | Screen::Paused) {
+                            *remapping = Some(Remapping::new());
+                            return true;
+                        }
+                        match key_bindings.borrow().control(key) {
+                            Some(control @ (Control::Left | Control::Right)) => {
+                                let action = if control == Control::Left { Action::Left } else { Action::Right };
+                                for action in auto_shift.borrow_mut().press(action) {
+                                    key_event_sender.send(Command::Action(action)).unwrap();
+                                }
                                 true
                             }
-                            Key::Left => {
-                                key_event_sender.send(Action::Left).unwrap();
+                            Some(control) => {
+                                key_event_sender.send(control.command()).unwrap();
                                 true
                             }
-                            Key::Right => {
-                                key_event_sender.send(Action::Right).unwrap();
+                            None => false,
+                        }
+                    }
+                    Event::KeyUp => {
+                        let key = event_key();
+                        held_keys.remove(&key);
+                        match key_bindings.borrow().control(key) {
+                            Some(Control::Left) => {
+                                auto_shift.borrow_mut().release(Action::Left);
+                                true
+                            }
+                            Some(Control::Right) => {
+                                auto_shift.borrow_mut().release(Action::Right);
                                 true
                             }
-                            Key::Down => {
-                                key_event_sender.send(Action::Drop).unwrap();
+                            Some(Control::SoftDrop) => {
+                                key_event_sender.send(Command::Action(Action::SoftDrop(false))).unwrap();
                                 true
                             }
                             _ => false,
@@ -66,7 +178,7 @@ impl Gui {
         }
         let offs = Offscreen::new(frame.width(), frame.height()).unwrap();
         offs.begin();
-        draw_rect_fill(0, 0, 200, 400, Color::White);
+        draw_rect_fill(0, 0, pixel_width, pixel_height, Color::White);
         offs.end();
 
         let offs = Rc::from(RefCell::from(offs));
@@ -77,34 +189,207 @@ impl Gui {
                 let mut offs = offs.borrow_mut();
                 if offs.is_valid() {
                     offs.rescale();
-                    offs.copy(0, 0, 200, 400, 0, 0);
+                    offs.copy(0, 0, pixel_width, pixel_height, 0, 0);
                 } else {
                     offs.begin();
-                    draw_rect_fill(0, 0, 200, 400, Color::White);
-                    offs.copy(0, 0, 200, 400, 0, 0);
+                    draw_rect_fill(0, 0, pixel_width, pixel_height, Color::White);
+                    offs.copy(0, 0, pixel_width, pixel_height, 0, 0);
                     offs.end();
                 }
             }
         });
 
+        let mut shapes = 0;
         while app.wait() {
-            if let Ok(blocks) = blocks_receiver.try_recv() {
+            // the game sends the state every tick, so only draw the latest
+            if let Some(state) = render_receiver.try_iter().last() {
+                if state.shapes != shapes {
+                    shapes = state.shapes;
+                    auto_shift.borrow_mut().new_shape();
+                }
                 let offs = offs.borrow_mut();
                 offs.begin();
-                for x in 0i8..10i8 {
-                    for y in 0i8..20i8 {
-                        let colour = if blocks[usize::from(x.unsigned_abs())][usize::from(y.unsigned_abs())] {
-                            Color::Black
-                        } else {
-                            Color::White
-                        };
-                        draw_rect_fill(i32::from(x) * 20, i32::from(y) * 20, 20, 20, colour);
-                        draw_rect_with_color(i32::from(x) * 20, i32::from(y) * 20, 20, 20, Color::White);
-                    }
+                Gui::draw_board(&state, width, height);
+                Gui::draw_hold_panel(&state, pixel_height);
+                Gui::draw_next_panel(&state, board_width + PANEL_WIDTH, pixel_height);
+                Gui::draw_screen(&state, &key_bindings.borrow(), board_width, pixel_height);
+                if let Some(remap) = remapping.borrow().as_ref() {
+                    Gui::draw_remapping(remap, &key_bindings.borrow(), pixel_width, pixel_height);
                 }
+                *screen.borrow_mut() = state.screen;
                 offs.end();
                 frame.redraw();
             }
         }
     }
-}
\ No newline at end of file
+
+    fn draw_board(state: &RenderState, width: i8, height: i8) {
+        let block_x = |x: i8| PANEL_WIDTH + i32::from(x) * 20;
+        for x in 0..width {
+            for y in 0..height {
+                let colour = Gui::colour(state.cells[usize::from(x.unsigned_abs())][usize::from(y.unsigned_abs())]);
+                draw_rect_fill(block_x(x), i32::from(y) * 20, 20, 20, colour);
+                draw_rect_with_color(block_x(x), i32::from(y) * 20, 20, 20, Color::White);
+            }
+        }
+        // the current shape is drawn over the ghost where they meet
+        if let Some((cell, blocks)) = state.ghost {
+            for block in blocks {
+                if block.y() >= 0 && state.cells[block.x() as usize][block.y() as usize] == Cell::Empty {
+                    draw_rect_with_color(block_x(block.x()) + 1, i32::from(block.y()) * 20 + 1, 18, 18, Gui::colour(cell));
+                }
+            }
+        }
+        if let Some((cell, blocks)) = state.active {
+            for block in blocks.iter().filter(|block| block.y() >= 0) {
+                draw_rect_fill(block_x(block.x()), i32::from(block.y()) * 20, 20, 20, Gui::colour(cell));
+                draw_rect_with_color(block_x(block.x()), i32::from(block.y()) * 20, 20, 20, Color::White);
+            }
+        }
+    }
+
+    fn draw_hold_panel(state: &RenderState, pixel_height: i32) {
+        draw_rect_fill(0, 0, PANEL_WIDTH, pixel_height, Color::Light2);
+        Gui::draw_label("HOLD", 10, 20);
+        if let Some(kind) = state.hold {
+            Gui::draw_preview(kind, 10, 30);
+        }
+        for (i, (label, value)) in Gui::stats(state).iter().enumerate() {
+            let y = 110 + i as i32 * 50;
+            Gui::draw_label(label, 10, y);
+            Gui::draw_label(value, 10, y + 20);
+        }
+    }
+
+    fn draw_next_panel(state: &RenderState, x: i32, pixel_height: i32) {
+        draw_rect_fill(x, 0, PANEL_WIDTH, pixel_height, Color::Light2);
+        Gui::draw_label("NEXT", x + 10, 20);
+        for (i, kind) in state.next.iter().enumerate() {
+            let y = 30 + i as i32 * 50;
+            if y + 50 > pixel_height {
+                break;
+            }
+            Gui::draw_preview(*kind, x + 10, y);
+        }
+    }
+
+    // the title, pause and game over screens are drawn in a box over the board
+    fn draw_screen(state: &RenderState, key_bindings: &KeyBindings, board_width: i32, pixel_height: i32) {
+        let lines = match state.screen {
+            Screen::Playing => return,
+            Screen::Title => vec![
+                "TETRIS".to_string(),
+                key_bindings.prompt(Control::Restart, "start"),
+                "F1 to change keys".to_string(),
+            ],
+            Screen::Paused => vec![
+                "PAUSED".to_string(),
+                key_bindings.prompt(Control::Pause, "resume"),
+                key_bindings.prompt(Control::Restart, "restart"),
+                "F1 to change keys".to_string(),
+            ],
+            Screen::GameOver => {
+                let mut lines = vec!["GAME OVER".to_string()];
+                lines.extend(Gui::stats(state).iter().map(|(label, value)| format!("{} {}", label, value)));
+                lines.push(key_bindings.prompt(Control::Restart, "play again"));
+                lines
+            }
+        };
+        let box_height = 20 + lines.len() as i32 * 20;
+        let top = (pixel_height - box_height) / 2;
+        draw_rect_fill(PANEL_WIDTH, top, board_width, box_height, Color::White);
+        draw_rect_with_color(PANEL_WIDTH, top, board_width, box_height, Color::Black);
+        for (i, line) in lines.iter().enumerate() {
+            Gui::draw_label(line, PANEL_WIDTH + 10, top + 25 + i as i32 * 20);
+        }
+    }
+
+    // the remapping screen covers the whole window, listing what each control is bound to
+    fn draw_remapping(remapping: &Remapping, key_bindings: &KeyBindings, pixel_width: i32, pixel_height: i32) {
+        draw_rect_fill(0, 0, pixel_width, pixel_height, Color::White);
+        Gui::draw_label("KEYS", 10, 20);
+        for (i, control) in Control::ALL.iter().enumerate() {
+            let y = 50 + i as i32 * 20;
+            let key_names: Vec<String> = key_bindings.keys(*control).iter().filter_map(|key| key_name(*key)).collect();
+            if remapping.control() == Some(*control) {
+                Gui::draw_label(">", 10, y);
+            }
+            Gui::draw_label(&control.name().replace('_', " ").to_uppercase(), 25, y);
+            Gui::draw_label(&key_names.join(", "), 150, y);
+        }
+        Gui::draw_label("Press a key, or F1 to keep", 10, 70 + Control::ALL.len() as i32 * 20);
+    }
+
+    fn stats(state: &RenderState) -> [(&'static str, String); 4] {
+        let seconds = state.time.as_secs();
+        [
+            ("SCORE", state.score.to_string()),
+            ("LEVEL", state.level.to_string()),
+            ("LINES", state.lines.to_string()),
+            ("TIME", format!("{}:{:02}.{}", seconds / 60, seconds % 60, state.time.subsec_millis() / 100)),
+        ]
+    }
+
+    fn draw_label(text: &str, x: i32, y: i32) {
+        set_font(Font::HelveticaBold, 14);
+        set_draw_color(Color::Black);
+        draw_text(text, x, y);
+    }
+
+    // draws the shape in its spawn rotation, centred in a 100 by 50 box
+    fn draw_preview(kind: ShapeKind, x: i32, y: i32) {
+        let blocks = Shape::of(kind).blocks(0, 0, 0);
+        let min_x = blocks.iter().map(|block| block.x()).min().unwrap();
+        let max_x = blocks.iter().map(|block| block.x()).max().unwrap();
+        let min_y = blocks.iter().map(|block| block.y()).min().unwrap();
+        let max_y = blocks.iter().map(|block| block.y()).max().unwrap();
+        let left = x + (100 - i32::from(max_x - min_x + 1) * PREVIEW_BLOCK_SIZE) / 2;
+        let top = y + (50 - i32::from(max_y - min_y + 1) * PREVIEW_BLOCK_SIZE) / 2;
+        for block in blocks {
+            let block_x = left + i32::from(block.x() - min_x) * PREVIEW_BLOCK_SIZE;
+            let block_y = top + i32::from(block.y() - min_y) * PREVIEW_BLOCK_SIZE;
+            draw_rect_fill(block_x, block_y, PREVIEW_BLOCK_SIZE, PREVIEW_BLOCK_SIZE, Gui::colour(Cell::of(kind)));
+            draw_rect_with_color(block_x, block_y, PREVIEW_BLOCK_SIZE, PREVIEW_BLOCK_SIZE, Color::White);
+        }
+    }
+
+    // the standard colours for each shape
+    fn colour(cell: Cell) -> Color {
+        match cell {
+            Cell::Empty => Color::White,
+            Cell::I => Color::from_rgb(0, 240, 240),
+            Cell::O => Color::from_rgb(240, 240, 0),
+            Cell::T => Color::from_rgb(160, 0, 240),
+            Cell::S => Color::from_rgb(0, 240, 0),
+            Cell::Z => Color::from_rgb(240, 0, 0),
+            Cell::J => Color::from_rgb(0, 0, 240),
+            Cell::L => Color::from_rgb(240, 160, 0),
+            Cell::Garbage => Color::from_rgb(128, 128, 128),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::tetris::Action::Drop;
+    use crate::tetris::tests::tetris_with_only_j_shape;
+    use super::*;
+
+    #[test]
+    fn should_render_the_locked_cells_apart_from_the_current_shape() {
+        // given
+        let mut tetris = tetris_with_only_j_shape();
+        tetris.input(&Drop);
+
+        // when
+        let state = RenderState::of(&tetris, Screen::Playing, Duration::from_secs(3));
+
+        // then
+        assert_eq!(Cell::J, state.cells[3][18]);
+        assert_eq!(Cell::Empty, state.cells[3][0]);
+        assert_eq!(Some((Cell::J, [Block::new(3, 0), Block::new(3, 1), Block::new(4, 1), Block::new(5, 1)])), state.active);
+        assert_eq!(vec![ShapeKind::J; 5], state.next);
+        assert_eq!(None, state.hold);
+        assert_eq!(Duration::from_secs(3), state.time);
+    }
+}
@@ -0,0 +1,225 @@
+use std::time::Duration;
+use crate::tetris::{Action, ActionResult, Config, Tetris};
+use crate::tetris::gui::RenderState;
+
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub enum Screen {
+    Title,
+    Playing,
+    Paused,
+    GameOver,
+}
+
+/// What the GUI sends the game
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub enum Command {
+    Action(Action),
+    /// Starts a fresh game from the title, pause or game over screens
+    NewGame,
+    /// Pauses or resumes the game
+    Pause,
+}
+
+/// A run of games in one window, moving between the screens
+pub struct Session {
+    config: Config,
+    tetris: Tetris,
+    screen: Screen,
+    time: Duration,
+    games: u64,
+}
+
+impl Session {
+    pub fn new(config: Config) -> Session {
+        Session {
+            tetris: Tetris::with_config(config.clone()),
+            config,
+            screen: Screen::Title,
+            time: Duration::ZERO,
+            games: 0,
+        }
+    }
+
+    pub fn screen(&self) -> Screen {
+        self.screen
+    }
+
+    pub fn tetris(&self) -> &Tetris {
+        &self.tetris
+    }
+
+    /// How long the current game has been played, not counting time paused
+    pub fn time(&self) -> Duration {
+        self.time
+    }
+
+    pub fn command(&mut self, command: Command) {
+        match (self.screen, command) {
+            (Screen::Playing, Command::Action(action)) => {
+                let result = self.tetris.input(&action);
+                self.end_if_over(result);
+            }
+            (Screen::Playing, Command::Pause) => {
+                // the key going up won't reach the game while it's paused
+                self.tetris.input(&Action::SoftDrop(false));
+                self.screen = Screen::Paused;
+            }
+            (Screen::Paused, Command::Pause) => self.screen = Screen::Playing,
+            (Screen::Title | Screen::Paused | Screen::GameOver, Command::NewGame) => {
+                // a seeded session plays a different but still reproducible sequence each game
+                self.tetris = Tetris::with_config(Config {
+                    seed: self.config.seed.map(|seed| seed.wrapping_add(self.games)),
+                    ..self.config.clone()
+                });
+                self.games += 1;
+                self.time = Duration::ZERO;
+                self.screen = Screen::Playing;
+            }
+            _ => {}
+        }
+    }
+
+    /// Moves the game on by the time since the last tick, unless it's not being played
+    pub fn tick(&mut self, elapsed: Duration) {
+        if self.screen == Screen::Playing {
+            self.time += elapsed;
+            let result = self.tetris.tick(elapsed);
+            self.end_if_over(result);
+        }
+    }
+
+    pub fn render_state(&self) -> RenderState {
+        RenderState::of(&self.tetris, self.screen, self.time)
+    }
+
+    fn end_if_over(&mut self, result: ActionResult) {
+        if let ActionResult::GameOver(..) = result {
+            self.screen = Screen::GameOver;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::tetris::Action::Drop;
+    use crate::tetris::ShapeKind;
+    use crate::tetris::tests::{config_with_only, timed_config_with_only};
+    use super::*;
+
+    #[test]
+    fn should_start_on_the_title_screen_ignoring_actions() {
+        // given
+        let mut session = Session::new(config_with_only(ShapeKind::J));
+
+        // when
+        session.command(Command::Action(Drop));
+        session.tick(Duration::from_secs(1));
+
+        // then
+        assert_eq!(Screen::Title, session.screen());
+        assert_eq!(0, session.tetris().score());
+        assert_eq!(Duration::ZERO, session.time());
+    }
+
+    #[test]
+    fn should_play_a_new_game() {
+        // given
+        let mut session = Session::new(config_with_only(ShapeKind::J));
+
+        // when
+        session.command(Command::NewGame);
+        session.command(Command::Action(Drop));
+
+        // then
+        assert_eq!(Screen::Playing, session.screen());
+        assert_eq!(36, session.tetris().score());
+    }
+
+    #[test]
+    fn should_stop_the_game_and_timer_while_paused() {
+        // given
+        let mut session = Session::new(timed_config_with_only(ShapeKind::J));
+        session.command(Command::NewGame);
+        session.tick(Duration::from_millis(200));
+
+        // when
+        session.command(Command::Pause);
+        session.command(Command::Action(Drop));
+        session.tick(Duration::from_secs(10));
+
+        // then
+        assert_eq!(Screen::Paused, session.screen());
+        assert_eq!(Duration::from_millis(200), session.time());
+        assert_eq!(0, session.tetris().score());
+    }
+
+    #[test]
+    fn should_resume_after_a_pause() {
+        // given
+        let mut session = Session::new(timed_config_with_only(ShapeKind::J));
+        session.command(Command::NewGame);
+        session.command(Command::Pause);
+
+        // when
+        session.command(Command::Pause);
+        session.tick(Duration::from_millis(200));
+
+        // then
+        assert_eq!(Screen::Playing, session.screen());
+        assert_eq!(Duration::from_millis(200), session.time());
+    }
+
+    #[test]
+    fn should_not_restart_while_playing() {
+        // given
+        let mut session = Session::new(config_with_only(ShapeKind::J));
+        session.command(Command::NewGame);
+        session.command(Command::Action(Drop));
+
+        // when
+        session.command(Command::NewGame);
+
+        // then
+        assert_eq!(36, session.tetris().score());
+    }
+
+    #[test]
+    fn should_deal_a_new_sequence_for_each_seeded_game() {
+        // given
+        let config = Config { seed: Some(7), ..Config::default() };
+        let mut session = Session::new(config.clone());
+        let mut replay = Session::new(config);
+        session.command(Command::NewGame);
+        let first_game = session.tetris().next_shapes();
+
+        // when
+        session.command(Command::Pause);
+        session.command(Command::NewGame);
+        replay.command(Command::NewGame);
+        replay.command(Command::Pause);
+        replay.command(Command::NewGame);
+
+        // then
+        assert_ne!(first_game, session.tetris().next_shapes());
+        assert_eq!(replay.tetris().next_shapes(), session.tetris().next_shapes());
+    }
+
+    #[test]
+    fn should_end_the_game_and_restart_with_a_fresh_one() {
+        // given
+        let mut session = Session::new(config_with_only(ShapeKind::J));
+        session.command(Command::NewGame);
+        for _ in 0..10 {
+            session.command(Command::Action(Drop));
+        }
+        assert_eq!(Screen::GameOver, session.screen());
+
+        // when
+        session.command(Command::NewGame);
+
+        // then
+        assert_eq!(Screen::Playing, session.screen());
+        assert_eq!(0, session.tetris().score());
+        assert_eq!(Duration::ZERO, session.time());
+    }
+}
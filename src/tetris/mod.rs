@@ -2,21 +2,94 @@ pub mod gui;
 
 pub mod bot;
 
-use rand::distributions::{Distribution, Uniform};
-use rand::rngs::ThreadRng;
-use rand::{Rng, RngCore, thread_rng};
-use crate::tetris::Action::Down;
+pub mod randomizer;
+
+pub mod scoring;
+
+pub mod rotation;
+
+pub mod board;
+
+pub mod timing;
+
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::time::Duration;
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use crate::tetris::board::Board;
+use crate::tetris::randomizer::{Bag, Randomizer};
+use crate::tetris::rotation::RotationSystem;
+use crate::tetris::scoring::{Scoring, ScoringSystem};
+use crate::tetris::timing::Timing;
 
 #[derive(Debug, PartialEq, Copy, Clone)]
 pub enum Action {
     Left,
     Right,
     Rotate,
+    RotateCcw,
+    Rotate180,
+    /// A step down as gravity would move the shape, which scores nothing
     Down,
+    /// Whether the soft drop is held, which speeds up gravity in games with timing, and is just
+    /// a move down in games without
+    SoftDrop(bool),
     Drop,
+    Hold,
 }
 
-#[derive(Copy, Clone)]
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub enum ShapeKind {
+    I,
+    O,
+    T,
+    S,
+    Z,
+    J,
+    L,
+}
+
+impl ShapeKind {
+    pub const ALL: [ShapeKind; 7] = [
+        ShapeKind::I,
+        ShapeKind::O,
+        ShapeKind::T,
+        ShapeKind::S,
+        ShapeKind::Z,
+        ShapeKind::J,
+        ShapeKind::L,
+    ];
+}
+
+/// What's in a cell of the board: nothing, a block of one of the shapes, or garbage
+#[derive(Debug, PartialEq, Eq, Hash, Copy, Clone)]
+pub enum Cell {
+    Empty,
+    I,
+    O,
+    T,
+    S,
+    Z,
+    J,
+    L,
+    Garbage,
+}
+
+impl Cell {
+    pub fn of(kind: ShapeKind) -> Cell {
+        match kind {
+            ShapeKind::I => Cell::I,
+            ShapeKind::O => Cell::O,
+            ShapeKind::T => Cell::T,
+            ShapeKind::S => Cell::S,
+            ShapeKind::Z => Cell::Z,
+            ShapeKind::J => Cell::J,
+            ShapeKind::L => Cell::L,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Hash, Copy, Clone)]
 pub struct Block {
     x: i8,
     y: i8,
@@ -26,28 +99,42 @@ impl Block {
     pub fn new(x: i8, y: i8) -> Block {
         Block { x, y }
     }
-    fn x(&self) -> i8 {
+    pub fn x(&self) -> i8 {
         self.x
     }
-    fn y(&self) -> i8 {
+    pub fn y(&self) -> i8 {
         self.y
     }
 }
 
 #[derive(Copy, Clone)]
 struct Shape {
+    kind: ShapeKind,
     base_rotations: [[Block; 4]; 4],
 }
 
 impl Shape {
-    fn new(base_rotations: [[Block; 4]; 4]) -> Shape {
+    fn new(kind: ShapeKind, base_rotations: [[Block; 4]; 4]) -> Shape {
         Shape {
+            kind,
             base_rotations
         }
     }
 
+    fn of(kind: ShapeKind) -> Shape {
+        match kind {
+            ShapeKind::I => Shape::i(),
+            ShapeKind::O => Shape::o(),
+            ShapeKind::T => Shape::t(),
+            ShapeKind::S => Shape::s(),
+            ShapeKind::Z => Shape::z(),
+            ShapeKind::J => Shape::j(),
+            ShapeKind::L => Shape::l(),
+        }
+    }
+
     pub fn o() -> Shape {
-        Shape::new([
+        Shape::new(ShapeKind::O, [
             [Block::new(4, 0), Block::new(5, 0), Block::new(4, 1), Block::new(5, 1)],
             [Block::new(4, 0), Block::new(5, 0), Block::new(4, 1), Block::new(5, 1)],
             [Block::new(4, 0), Block::new(5, 0), Block::new(4, 1), Block::new(5, 1)],
@@ -56,7 +143,7 @@ impl Shape {
     }
 
     pub fn s() -> Shape {
-        Shape::new([
+        Shape::new(ShapeKind::S, [
             [Block::new(4, 0), Block::new(5, 0), Block::new(3, 1), Block::new(4, 1)],
             [Block::new(4, 0), Block::new(4, 1), Block::new(5, 1), Block::new(5, 2)],
             [Block::new(4, 1), Block::new(5, 1), Block::new(3, 2), Block::new(4, 2)],
@@ -65,7 +152,7 @@ impl Shape {
     }
 
     pub fn z() -> Shape {
-        Shape::new([
+        Shape::new(ShapeKind::Z, [
             [Block::new(3, 0), Block::new(4, 0), Block::new(4, 1), Block::new(5, 1)],
             [Block::new(5, 0), Block::new(5, 1), Block::new(4, 1), Block::new(4, 2)],
             [Block::new(3, 1), Block::new(4, 1), Block::new(4, 2), Block::new(5, 2)],
@@ -73,7 +160,7 @@ impl Shape {
         ])
     }
     pub fn t() -> Shape {
-        Shape::new([
+        Shape::new(ShapeKind::T, [
             [Block::new(4, 0), Block::new(3, 1), Block::new(4, 1), Block::new(5, 1)],
             [Block::new(4, 0), Block::new(4, 1), Block::new(4, 2), Block::new(5, 1)],
             [Block::new(4, 2), Block::new(3, 1), Block::new(4, 1), Block::new(5, 1)],
@@ -81,7 +168,7 @@ impl Shape {
         ])
     }
     pub fn l() -> Shape {
-        Shape::new([
+        Shape::new(ShapeKind::L, [
             [Block::new(5, 0), Block::new(3, 1), Block::new(4, 1), Block::new(5, 1)],
             [Block::new(4, 0), Block::new(4, 1), Block::new(4, 2), Block::new(5, 2)],
             [Block::new(3, 2), Block::new(3, 1), Block::new(4, 1), Block::new(5, 1)],
@@ -89,7 +176,7 @@ impl Shape {
         ])
     }
     pub fn j() -> Shape {
-        Shape::new([
+        Shape::new(ShapeKind::J, [
             [Block::new(3, 0), Block::new(3, 1), Block::new(4, 1), Block::new(5, 1)],
             [Block::new(4, 0), Block::new(5, 0), Block::new(4, 1), Block::new(4, 2)],
             [Block::new(5, 2), Block::new(3, 1), Block::new(4, 1), Block::new(5, 1)],
@@ -97,7 +184,7 @@ impl Shape {
         ])
     }
     pub fn i() -> Shape {
-        Shape::new([
+        Shape::new(ShapeKind::I, [
             [Block::new(3, 1), Block::new(4, 1), Block::new(5, 1), Block::new(6, 1)],
             [Block::new(5, 0), Block::new(5, 1), Block::new(5, 2), Block::new(5, 3)],
             [Block::new(3, 2), Block::new(4, 2), Block::new(5, 2), Block::new(6, 2)],
@@ -105,207 +192,782 @@ impl Shape {
         ])
     }
 
-    fn has_block_at(&self, x: i8, y: i8, rotations: usize, x_diff: i8, y_diff: i8) -> bool {
+    fn blocks(&self, rotations: usize, x_diff: i8, y_diff: i8) -> [Block; 4] {
         let base_rotation = rotations % self.base_rotations.len();
-        let blocks = self.base_rotations[base_rotation];
-        let mut matches = false;
-        for block in blocks {
-            matches = block.x() == x - x_diff && block.y() == y - y_diff;
-            if matches {
-                break;
-            }
-        }
-        matches
+        self.base_rotations[base_rotation].map(|block| Block::new(block.x() + x_diff, block.y() + y_diff))
     }
+}
 
-    fn is_off_grid(&self, rotations: usize, x_diff: i8, y_diff: i8) -> bool {
-        let base_rotation = rotations % self.base_rotations.len();
-        let blocks = self.base_rotations[base_rotation];
-        let mut off_grid: bool = false;
-        for block in blocks {
-            let x = block.x() + x_diff;
-            let y = block.y() + y_diff;
-            off_grid = x > 9 || x < 0 || y > 19 || y < 0;
-            if off_grid {
-                break;
-            }
-        }
-        off_grid
-    }
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub enum LineClear {
+    None,
+    Single,
+    Double,
+    Triple,
+    Tetris,
+}
 
-    fn apply_to(&self, grid: &mut [[bool; 20]; 10], rotations: usize, x_diff: i8, y_diff: i8) {
-        let base_rotation = rotations % self.base_rotations.len();
-        let blocks = self.base_rotations[base_rotation];
-        for block in blocks {
-            let x = block.x() + x_diff;
-            let y = block.y() + y_diff;
-            grid[usize::from(x.unsigned_abs())][usize::from(y.unsigned_abs())] = true;
+impl LineClear {
+    fn of(lines: usize) -> LineClear {
+        match lines {
+            0 => LineClear::None,
+            1 => LineClear::Single,
+            2 => LineClear::Double,
+            3 => LineClear::Triple,
+            _ => LineClear::Tetris,
         }
     }
+}
 
-    fn intersects(&self, grid: &[[bool; 20]; 10], rotations: usize, x_diff: i8, y_diff: i8) -> bool {
-        let base_rotation = rotations % self.base_rotations.len();
-        let blocks = self.base_rotations[base_rotation];
-        for block in blocks {
-            let x = block.x() + x_diff;
-            let y = block.y() + y_diff;
-            if grid[usize::from(x.unsigned_abs())][usize::from(y.unsigned_abs())] {
-                return true;
-            }
-        }
-        false
-    }
+#[derive(Debug, PartialEq, PartialOrd, Copy, Clone)]
+pub enum TSpin {
+    None,
+    Mini,
+    Full,
+}
+
+#[derive(PartialEq, Debug, Clone)]
+pub struct Lock {
+    pub shape: ShapeKind,
+    pub rotation: usize,
+    pub blocks: [Block; 4],
+    pub cleared_rows: Vec<i8>,
+    pub line_clear: LineClear,
+    pub t_spin: TSpin,
+    pub combo: Option<u32>,
+    pub back_to_back: bool,
+    pub perfect_clear: bool,
+}
+
+/// Where the current shape is: its rotation, and how far it has moved from where it's defined
+#[derive(Debug, PartialEq, Eq, Hash, Copy, Clone)]
+pub struct Placement {
+    pub rotation: usize,
+    pub x: i8,
+    pub y: i8,
+}
+
+// which of the rotation system's offsets the last rotation took to fit
+#[derive(Debug, PartialEq, Eq, Hash, Copy, Clone)]
+struct Kick {
+    index: usize,
+    half_turn: bool,
+}
+
+// where the search for locks got to, with the kick of the rotation that got it there and the step
+// it came from with the action that moved it
+#[derive(Copy, Clone)]
+struct SearchStep {
+    placement: Placement,
+    kick: Option<Kick>,
+    previous: Option<(usize, Action)>,
+}
+
+struct ReachableLock {
+    placement: Placement,
+    kick: Option<Kick>,
+    actions: Vec<Action>,
+}
+
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub enum GameOverReason {
+    /// A new shape overlapped the stack where it spawned
+    BlockOut,
+    /// A shape locked entirely inside the vanish zone above the visible rows
+    LockOut,
 }
 
 #[derive(PartialEq, Debug)]
 pub enum ActionResult {
     Invalid,
     CurrentShape,
-    NextShape,
-    GameOver,
+    NextShape(Lock),
+    /// Why the game ended, with the lock that ended it unless it was a hold
+    GameOver(GameOverReason, Option<Lock>),
 }
 
-pub struct Tetris {
-    shape_count: usize,
-    shapes: [Shape; 7],
-    rng: ThreadRng,
+#[derive(Clone)]
+pub struct Config {
+    pub randomizer: Box<dyn Randomizer>,
+    pub seed: Option<u64>,
+    pub preview_size: usize,
+    pub scoring: ScoringSystem,
+    pub rotation_system: RotationSystem,
+    pub width: u8,
+    pub height: u8,
+    pub vanish_rows: u8,
+    /// How long things take when the game is played in real time with tick, or nothing for
+    /// shapes to lock as soon as they land
+    pub timing: Option<Timing>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            randomizer: Box::new(Bag::new()),
+            seed: None,
+            preview_size: 5,
+            scoring: ScoringSystem::Guideline,
+            rotation_system: RotationSystem::Srs,
+            width: 10,
+            height: 20,
+            vanish_rows: 20,
+            timing: None,
+        }
+    }
+}
+
+/// Everything about a game that changes as it's played, so it can be put back as it was
+#[derive(Clone)]
+pub struct Snapshot {
+    randomizer: Box<dyn Randomizer>,
+    rng: StdRng,
+    next_shapes: VecDeque<ShapeKind>,
+    current_shape: Shape,
+    held_shape: Option<ShapeKind>,
+    hold_used: bool,
+    scoring: Scoring,
+    last_kick: Option<Kick>,
     current_shape_rotations: usize,
     current_shape_x_diff: i8,
     current_shape_y_diff: i8,
-    dead_blocks: [[bool; 20]; 10],
+    dead_blocks: Board,
+    entry_delay: Duration,
+    gravity_elapsed: Duration,
+    lock_elapsed: Duration,
+    lock_resets: u32,
+    lowest_y_diff: i8,
+    soft_dropping: bool,
+    shapes: u32,
 }
 
-impl Clone for Tetris {
-    fn clone(&self) -> Self {
-        let mut rng = thread_rng();
-        Tetris {
-            shape_count: self.shape_count,
-            shapes: self.shapes,
-            rng,
-            current_shape_rotations: self.current_shape_rotations,
-            current_shape_x_diff: self.current_shape_x_diff,
-            current_shape_y_diff: self.current_shape_y_diff,
-            dead_blocks: self.dead_blocks,
-        }
-    }
+#[derive(Clone)]
+pub struct Tetris {
+    randomizer: Box<dyn Randomizer>,
+    rng: StdRng,
+    preview_size: usize,
+    next_shapes: VecDeque<ShapeKind>,
+    current_shape: Shape,
+    held_shape: Option<ShapeKind>,
+    hold_used: bool,
+    scoring: Scoring,
+    rotation_system: RotationSystem,
+    timing: Option<Timing>,
+    last_kick: Option<Kick>,
+    current_shape_rotations: usize,
+    current_shape_x_diff: i8,
+    current_shape_y_diff: i8,
+    dead_blocks: Board,
+    entry_delay: Duration,
+    gravity_elapsed: Duration,
+    lock_elapsed: Duration,
+    lock_resets: u32,
+    lowest_y_diff: i8,
+    soft_dropping: bool,
+    shapes: u32,
 }
 
 impl Tetris {
     pub fn new() -> Tetris {
-        Tetris::new_with_custom_shapes([
-            Shape::o(),
-            Shape::s(),
-            Shape::z(),
-            Shape::t(),
-            Shape::l(),
-            Shape::j(),
-            Shape::i(),
-        ])
+        Tetris::with_config(Config::default())
     }
 
-    fn new_with_custom_shapes(shapes: [Shape; 7]) -> Tetris {
-        let rng = thread_rng();
-        Tetris {
-            shape_count: 0,
-            shapes,
+    pub fn with_seed(seed: u64) -> Tetris {
+        Tetris::with_config(Config {
+            seed: Some(seed),
+            ..Config::default()
+        })
+    }
+
+    /// # Panics
+    ///
+    /// If the board is narrower or shorter than 4 blocks, wider or taller than 64, or has more
+    /// than 40 vanish rows
+    pub fn with_config(config: Config) -> Tetris {
+        let mut randomizer = config.randomizer;
+        let mut rng = match config.seed {
+            Some(seed) => StdRng::seed_from_u64(seed),
+            None => StdRng::from_entropy(),
+        };
+        let mut next_shapes = VecDeque::new();
+        for _ in 0..config.preview_size + 1 {
+            next_shapes.push_back(randomizer.next(&mut rng));
+        }
+        let current_shape = Shape::of(next_shapes.pop_front().unwrap());
+        let dead_blocks = Board::new(config.width, config.height, config.vanish_rows);
+        let mut tetris = Tetris {
+            randomizer,
             rng,
+            preview_size: config.preview_size,
+            next_shapes,
+            current_shape,
+            held_shape: None,
+            hold_used: false,
+            scoring: Scoring::new(config.scoring),
+            rotation_system: config.rotation_system,
+            timing: config.timing,
+            last_kick: None,
             current_shape_rotations: 0,
             current_shape_x_diff: 0,
             current_shape_y_diff: 0,
-            dead_blocks: [[false; 20]; 10],
+            dead_blocks,
+            entry_delay: Duration::ZERO,
+            gravity_elapsed: Duration::ZERO,
+            lock_elapsed: Duration::ZERO,
+            lock_resets: 0,
+            lowest_y_diff: 0,
+            soft_dropping: false,
+            shapes: 0,
+        };
+        tetris.spawn(current_shape);
+        tetris
+    }
+
+    pub fn snapshot(&self) -> Snapshot {
+        Snapshot {
+            randomizer: self.randomizer.clone(),
+            rng: self.rng.clone(),
+            next_shapes: self.next_shapes.clone(),
+            current_shape: self.current_shape,
+            held_shape: self.held_shape,
+            hold_used: self.hold_used,
+            scoring: self.scoring.clone(),
+            last_kick: self.last_kick,
+            current_shape_rotations: self.current_shape_rotations,
+            current_shape_x_diff: self.current_shape_x_diff,
+            current_shape_y_diff: self.current_shape_y_diff,
+            dead_blocks: self.dead_blocks.clone(),
+            entry_delay: self.entry_delay,
+            gravity_elapsed: self.gravity_elapsed,
+            lock_elapsed: self.lock_elapsed,
+            lock_resets: self.lock_resets,
+            lowest_y_diff: self.lowest_y_diff,
+            shapes: self.shapes,
+            soft_dropping: self.soft_dropping,
         }
     }
 
+    /// Puts the game back as it was when the snapshot was taken, reusing the memory it already has
+    pub fn restore(&mut self, snapshot: &Snapshot) {
+        self.randomizer.clone_from(&snapshot.randomizer);
+        self.rng.clone_from(&snapshot.rng);
+        self.next_shapes.clone_from(&snapshot.next_shapes);
+        self.current_shape = snapshot.current_shape;
+        self.held_shape = snapshot.held_shape;
+        self.hold_used = snapshot.hold_used;
+        self.scoring.clone_from(&snapshot.scoring);
+        self.last_kick = snapshot.last_kick;
+        self.current_shape_rotations = snapshot.current_shape_rotations;
+        self.current_shape_x_diff = snapshot.current_shape_x_diff;
+        self.current_shape_y_diff = snapshot.current_shape_y_diff;
+        self.dead_blocks.clone_from(&snapshot.dead_blocks);
+        self.entry_delay = snapshot.entry_delay;
+        self.gravity_elapsed = snapshot.gravity_elapsed;
+        self.lock_elapsed = snapshot.lock_elapsed;
+        self.lock_resets = snapshot.lock_resets;
+        self.lowest_y_diff = snapshot.lowest_y_diff;
+        self.shapes = snapshot.shapes;
+        self.soft_dropping = snapshot.soft_dropping;
+    }
+
+    pub fn width(&self) -> i8 {
+        self.dead_blocks.width()
+    }
+
+    pub fn height(&self) -> i8 {
+        self.dead_blocks.height()
+    }
+
+    pub fn vanish_rows(&self) -> i8 {
+        self.dead_blocks.vanish_rows()
+    }
+
+    pub fn current_shape(&self) -> ShapeKind {
+        self.current_shape.kind
+    }
+
+    pub fn next_shapes(&self) -> Vec<ShapeKind> {
+        self.next_shapes.iter().copied().collect()
+    }
+
+    pub fn held_shape(&self) -> Option<ShapeKind> {
+        self.held_shape
+    }
+
+    /// How many shapes have come into play, including ones swapped in by holding
+    pub fn shapes(&self) -> u32 {
+        self.shapes
+    }
+
+    pub fn score(&self) -> u32 {
+        self.scoring.score()
+    }
+
+    pub fn lines(&self) -> u32 {
+        self.scoring.lines()
+    }
+
+    pub fn level(&self) -> u32 {
+        self.scoring.level()
+    }
+
+    pub fn combo(&self) -> Option<u32> {
+        self.scoring.combo()
+    }
+
+    pub fn max_combo(&self) -> u32 {
+        self.scoring.max_combo()
+    }
+
+    pub fn back_to_back_streak(&self) -> u32 {
+        self.scoring.back_to_back_streak()
+    }
+
+    pub fn back_to_backs(&self) -> u32 {
+        self.scoring.back_to_backs()
+    }
+
+    pub fn perfect_clears(&self) -> u32 {
+        self.scoring.perfect_clears()
+    }
+
     pub fn block_at(&self, x: i8, y: i8) -> bool {
-        let is_dead_block = self.dead_blocks[usize::from(x.unsigned_abs())][usize::from(y.unsigned_abs())];
-        let shape = self.shapes[self.shape_count % 7];
-        let is_current_shape = shape.has_block_at(x, y, self.current_shape_rotations, self.current_shape_x_diff, self.current_shape_y_diff);
+        let is_dead_block = self.dead_blocks.is_filled(x, y);
+        let is_current_shape = !self.is_entering() && self.current_blocks().contains(&Block::new(x, y));
         is_dead_block || is_current_shape
     }
 
-    fn validate_and_place(&mut self, rotations: usize, x_diff: i8, y_diff: i8) -> ActionResult {
-        let shape = self.shapes[self.shape_count % 7];
-        let valid = !shape.is_off_grid(rotations, x_diff, y_diff) &&
-            !shape.intersects(&self.dead_blocks, rotations, x_diff, y_diff);
-        if valid {
-            let shape_finished = shape.is_off_grid(rotations, x_diff, y_diff + 1) ||
-                shape.intersects(&self.dead_blocks, rotations, x_diff, y_diff + 1);
-            if shape_finished {
-                shape.apply_to(&mut self.dead_blocks, rotations, x_diff, y_diff);
-                self.complete_lines();
-                self.shape_count = self.shape_count + 1;
-                self.shuffle_shapes();
-                self.current_shape_rotations = 0;
-                self.current_shape_x_diff = 0;
-                self.current_shape_y_diff = 0;
-                let shape = self.shapes[self.shape_count % 7];
-                if shape.intersects(&self.dead_blocks, 0, 0, 1) {
-                    ActionResult::GameOver
-                } else {
-                    ActionResult::NextShape
+    /// What's in the cell, including the current shape
+    pub fn cell_at(&self, x: i8, y: i8) -> Cell {
+        if !self.is_entering() && self.current_blocks().contains(&Block::new(x, y)) {
+            Cell::of(self.current_shape.kind)
+        } else {
+            self.dead_blocks.cell_at(x, y)
+        }
+    }
+
+    /// What's in the cell, leaving out the current shape
+    pub fn locked_cell_at(&self, x: i8, y: i8) -> Cell {
+        self.dead_blocks.cell_at(x, y)
+    }
+
+    /// Where the current shape is, or nothing while waiting for it to appear
+    pub fn active_blocks(&self) -> Option<[Block; 4]> {
+        if self.is_entering() {
+            None
+        } else {
+            Some(self.current_blocks())
+        }
+    }
+
+    /// How many rows the current shape would fall on a hard drop
+    pub fn drop_distance(&self) -> i8 {
+        self.dead_blocks.drop_distance(&self.current_blocks())
+    }
+
+    /// Where the current shape would land on a hard drop, or nothing while waiting for it to appear
+    pub fn ghost(&self) -> Option<[Block; 4]> {
+        if self.is_entering() {
+            return None;
+        }
+        let placement = self.current_placement();
+        Some(self.current_shape.blocks(placement.rotation, placement.x, placement.y + self.drop_distance()))
+    }
+
+    fn current_blocks(&self) -> [Block; 4] {
+        self.current_shape.blocks(self.current_shape_rotations, self.current_shape_x_diff, self.current_shape_y_diff)
+    }
+
+    fn fits(&self, rotations: usize, x_diff: i8, y_diff: i8) -> bool {
+        self.dead_blocks.fits(&self.current_shape.blocks(rotations, x_diff, y_diff))
+    }
+
+    // The 3-corner rule: a T that was rotated into place with three of the four corners around its
+    // centre blocked is a spin, and a mini unless both corners it points at are blocked or it took
+    // the last, long kick of a quarter turn to get there
+    fn t_spin(&self, kick: Option<Kick>, placement: &Placement) -> TSpin {
+        let kick = match kick {
+            Some(kick) if self.current_shape.kind == ShapeKind::T => kick,
+            _ => return TSpin::None,
+        };
+        let x = 4 + placement.x;
+        let y = 1 + placement.y;
+        let corners = [(-1, -1), (1, -1), (1, 1), (-1, 1)]
+            .map(|(x_corner, y_corner)| self.dead_blocks.is_blocked(x + x_corner, y + y_corner));
+        if corners.iter().filter(|blocked| **blocked).count() < 3 {
+            return TSpin::None;
+        }
+        let front = placement.rotation % 4;
+        if (corners[front] && corners[(front + 1) % 4]) || (!kick.half_turn && kick.index == 4) {
+            TSpin::Full
+        } else {
+            TSpin::Mini
+        }
+    }
+
+    fn validate_and_place(&mut self, rotations: usize, x_diff: i8, y_diff: i8, kick: Option<Kick>) -> ActionResult {
+        if self.fits(rotations, x_diff, y_diff) {
+            self.last_kick = kick;
+            self.current_shape_rotations = rotations;
+            self.current_shape_x_diff = x_diff;
+            self.current_shape_y_diff = y_diff;
+            let shape_finished = !self.fits(rotations, x_diff, y_diff + 1);
+            match self.timing {
+                None if shape_finished => self.lock_current_shape(),
+                None => ActionResult::CurrentShape,
+                Some(timing) => {
+                    self.lock_elapsed = Duration::ZERO;
+                    if y_diff > self.lowest_y_diff {
+                        // falling further than before gives back all the lock resets
+                        self.lowest_y_diff = y_diff;
+                        self.lock_resets = 0;
+                    } else if shape_finished {
+                        if self.lock_resets >= timing.lock_resets {
+                            return self.lock_current_shape();
+                        }
+                        self.lock_resets += 1;
+                    }
+                    ActionResult::CurrentShape
                 }
-            } else {
-                self.current_shape_rotations = rotations;
-                self.current_shape_x_diff = x_diff;
-                self.current_shape_y_diff = y_diff;
-                ActionResult::CurrentShape
             }
         } else {
             ActionResult::Invalid
         }
     }
 
-    fn shuffle_shapes(&mut self) {
-        if self.shape_count % 7 == 0 {
-            for i in 0..self.shapes.len() - 2 {
-                let j = thread_rng().gen_range(0..i + 1);
-                let shape_from_i = self.shapes[i];
-                self.shapes[i] = self.shapes[j];
-                self.shapes[j] = shape_from_i;
+    fn lock_current_shape(&mut self) -> ActionResult {
+        let shape = self.current_shape;
+        let rotations = self.current_shape_rotations;
+        let t_spin = self.t_spin(self.last_kick, &self.current_placement());
+        let blocks = self.current_blocks();
+        self.dead_blocks.fill(&blocks, Cell::of(shape.kind));
+        let cleared_rows = self.dead_blocks.clear_lines();
+        let perfect_clear = !cleared_rows.is_empty() && self.dead_blocks.is_empty();
+        self.scoring.lock(cleared_rows.len() as u32, t_spin, perfect_clear);
+        let back_to_back = !cleared_rows.is_empty() && self.scoring.is_back_to_back();
+        let lock = Lock {
+            shape: shape.kind,
+            rotation: rotations % 4,
+            blocks,
+            line_clear: LineClear::of(cleared_rows.len()),
+            cleared_rows,
+            t_spin,
+            combo: self.scoring.combo(),
+            back_to_back,
+            perfect_clear,
+        };
+        if blocks.iter().all(|block| block.y() < 0) {
+            return ActionResult::GameOver(GameOverReason::LockOut, Some(lock));
+        }
+        if let Some(timing) = self.timing {
+            self.entry_delay = timing.entry_delay;
+            if !lock.cleared_rows.is_empty() {
+                self.entry_delay += timing.line_clear_delay;
+            }
+        }
+        let next_shape = self.take_next_shape();
+        self.hold_used = false;
+        if self.spawn(next_shape) {
+            ActionResult::NextShape(lock)
+        } else {
+            ActionResult::GameOver(GameOverReason::BlockOut, Some(lock))
+        }
+    }
+
+    fn spawn(&mut self, shape: Shape) -> bool {
+        self.current_shape = shape;
+        self.shapes += 1;
+        self.current_shape_rotations = 0;
+        // shapes are defined in the middle of a board 10 blocks wide
+        self.current_shape_x_diff = (self.dead_blocks.width() - 10) / 2;
+        // shapes spawn in the two rows above the visible ones, then drop straight into view
+        self.current_shape_y_diff = -self.dead_blocks.vanish_rows().min(2);
+        self.last_kick = None;
+        if !self.fits(0, self.current_shape_x_diff, self.current_shape_y_diff) {
+            return false;
+        }
+        if self.current_shape_y_diff < 0 && self.fits(0, self.current_shape_x_diff, self.current_shape_y_diff + 1) {
+            self.current_shape_y_diff += 1;
+        }
+        self.gravity_elapsed = Duration::ZERO;
+        self.lock_elapsed = Duration::ZERO;
+        self.lock_resets = 0;
+        self.lowest_y_diff = self.current_shape_y_diff;
+        true
+    }
+
+    fn hold(&mut self) -> ActionResult {
+        if self.hold_used {
+            return ActionResult::Invalid;
+        }
+        let shape = match self.held_shape {
+            Some(held_shape) => Shape::of(held_shape),
+            None => self.take_next_shape(),
+        };
+        self.held_shape = Some(self.current_shape.kind);
+        self.hold_used = true;
+        if self.spawn(shape) {
+            ActionResult::CurrentShape
+        } else {
+            ActionResult::GameOver(GameOverReason::BlockOut, None)
+        }
+    }
+
+    // Where a movement would take the shape from the placement, with the kick if it rotated
+    fn step(&self, placement: &Placement, action: &Action) -> Option<(Placement, Option<Kick>)> {
+        let Placement { rotation, x, y } = *placement;
+        let rotations = match action {
+            Action::Left => return self.fits(rotation, x - 1, y).then_some((Placement { rotation, x: x - 1, y }, None)),
+            Action::Right => return self.fits(rotation, x + 1, y).then_some((Placement { rotation, x: x + 1, y }, None)),
+            Action::Down => return self.fits(rotation, x, y + 1).then_some((Placement { rotation, x, y: y + 1 }, None)),
+            Action::Rotate => 1,
+            Action::RotateCcw => 3,
+            Action::Rotate180 => 2,
+            Action::SoftDrop(_) | Action::Drop | Action::Hold => return None,
+        };
+        let to = (rotation + rotations) % 4;
+        self.rotation_system.kicks(self.current_shape.kind, rotation, to).iter()
+            .enumerate()
+            .find(|(_, (x_kick, y_kick))| self.fits(to, x + x_kick, y + y_kick))
+            .map(|(index, (x_kick, y_kick))| {
+                (Placement { rotation: to, x: x + x_kick, y: y + y_kick }, Some(Kick { index, half_turn: rotations == 2 }))
+            })
+    }
+
+    // Every distinct lock the current shape can reach, found breadth first so each comes with the
+    // shortest way there, or for a T with the way that spins it in
+    fn reachable_locks(&self) -> Vec<ReachableLock> {
+        // there's no shape to move while the next one is entering
+        if self.is_entering() {
+            return vec![];
+        }
+        // a T rotated into a place isn't the same as one moved there, as only one of them can spin
+        let is_t = self.current_shape.kind == ShapeKind::T;
+        let start = SearchStep { placement: self.current_placement(), kick: self.last_kick, previous: None };
+        let mut steps = vec![start];
+        let mut visited = HashSet::from([(start.placement, start.kick.filter(|_| is_t))]);
+        let mut lock_indices = HashMap::new();
+        let mut locks = vec![];
+        let mut index = 0;
+        while index < steps.len() {
+            let step = steps[index];
+            // a hard drop locks from anywhere, only keeping the kick if the shape doesn't fall
+            let rows = self.dead_blocks.drop_distance(&self.placement_blocks(&step.placement));
+            self.add_lock(&mut locks, &mut lock_indices, SearchStep {
+                placement: Placement { y: step.placement.y + rows, ..step.placement },
+                kick: if rows == 0 { step.kick } else { None },
+                previous: Some((index, Action::Drop)),
+            });
+            if rows > 0 || !self.locks_on_touch() {
+                // rotations first, so that ways of the same length rotate before moving like players do
+                for action in [Action::Rotate, Action::RotateCcw, Action::Rotate180, Action::Left, Action::Right, Action::Down] {
+                    if let Some((placement, kick)) = self.step(&step.placement, &action) {
+                        if !visited.insert((placement, kick.filter(|_| is_t))) {
+                            continue;
+                        }
+                        let next = SearchStep { placement, kick, previous: Some((index, action)) };
+                        if self.locks_on_touch() && self.is_resting(&placement) {
+                            self.add_lock(&mut locks, &mut lock_indices, next);
+                        } else {
+                            steps.push(next);
+                        }
+                    }
+                }
             }
+            index += 1;
         }
+        locks.iter()
+            .map(|lock| {
+                let mut actions = vec![];
+                let mut previous = lock.previous;
+                while let Some((index, action)) = previous {
+                    actions.push(action);
+                    previous = steps[index].previous;
+                }
+                actions.reverse();
+                ReachableLock { placement: lock.placement, kick: lock.kick, actions }
+            })
+            .collect()
     }
 
-    fn complete_lines(&mut self) {
-        let mut completed_lines = 0;
-        for y in (0u8..20u8).rev() {
-            let mut line_complete = true;
-            for x in 0u8..10u8 {
-                let current_block_present = self.dead_blocks[usize::from(x)][usize::from(y)];
-                line_complete = line_complete && current_block_present;
-                self.dead_blocks[usize::from(x)][usize::from(y + completed_lines)] = current_block_present;
-                if completed_lines > 0 {
-                    self.dead_blocks[usize::from(x)][usize::from(y)] = false
+    // Adds the lock unless the same blocks are already locked, keeping whichever way there makes
+    // the better spin
+    fn add_lock(&self, locks: &mut Vec<SearchStep>, lock_indices: &mut HashMap<[Block; 4], usize>, lock: SearchStep) {
+        let mut blocks = self.placement_blocks(&lock.placement);
+        blocks.sort_by_key(|block| (block.y(), block.x()));
+        match lock_indices.get(&blocks) {
+            Some(&index) => {
+                let found = locks[index];
+                if self.t_spin(lock.kick, &lock.placement) > self.t_spin(found.kick, &found.placement) {
+                    locks[index] = lock;
                 }
             }
-            if line_complete {
-                completed_lines = completed_lines + 1;
+            None => {
+                lock_indices.insert(blocks, locks.len());
+                locks.push(lock);
+            }
+        }
+    }
+
+    fn take_next_shape(&mut self) -> Shape {
+        while self.next_shapes.len() <= self.preview_size {
+            let shape = self.randomizer.next(&mut self.rng);
+            self.next_shapes.push_back(shape);
+        }
+        Shape::of(self.next_shapes.pop_front().unwrap())
+    }
+
+    /// Whether shapes lock as soon as they land, rather than after a lock delay
+    pub fn locks_on_touch(&self) -> bool {
+        self.timing.is_none()
+    }
+
+    /// Whether the current shape is waiting out an entry or line clear delay before it appears
+    pub fn is_entering(&self) -> bool {
+        !self.entry_delay.is_zero()
+    }
+
+    /// Moves the game on by some time, letting the current shape fall and lock under the timing it
+    /// was configured with. Games without timing only move when they're given input
+    pub fn tick(&mut self, elapsed: Duration) -> ActionResult {
+        let timing = match self.timing {
+            Some(timing) => timing,
+            None => return ActionResult::CurrentShape,
+        };
+        let mut elapsed = elapsed;
+        if self.is_entering() {
+            if elapsed < self.entry_delay {
+                self.entry_delay -= elapsed;
+                return ActionResult::CurrentShape;
+            }
+            elapsed -= self.entry_delay;
+            self.entry_delay = Duration::ZERO;
+        }
+        let row_time = if self.soft_dropping {
+            // no multiplier drops the shape straight to the stack rather than dividing by zero
+            timing.gravity(&self.scoring).checked_div(timing.soft_drop_multiplier).unwrap_or(Duration::ZERO)
+        } else {
+            timing.gravity(&self.scoring)
+        };
+        self.gravity_elapsed += elapsed;
+        while self.gravity_elapsed >= row_time && !self.is_resting(&self.current_placement()) {
+            self.gravity_elapsed -= row_time;
+            self.validate_and_place(self.current_shape_rotations, self.current_shape_x_diff, self.current_shape_y_diff + 1, None);
+            if self.soft_dropping {
+                self.scoring.soft_drop(1);
+            }
+        }
+        if self.is_resting(&self.current_placement()) {
+            // only the time since it landed counts towards locking it
+            self.lock_elapsed += self.gravity_elapsed;
+            self.gravity_elapsed = Duration::ZERO;
+            if self.lock_elapsed >= timing.lock_delay {
+                return self.lock_current_shape();
+            }
+        }
+        ActionResult::CurrentShape
+    }
+
+    pub fn current_placement(&self) -> Placement {
+        Placement {
+            rotation: self.current_shape_rotations,
+            x: self.current_shape_x_diff,
+            y: self.current_shape_y_diff,
+        }
+    }
+
+    pub fn placement_blocks(&self, placement: &Placement) -> [Block; 4] {
+        self.current_shape.blocks(placement.rotation, placement.x, placement.y)
+    }
+
+    /// Where the current shape would be after the action, or nothing if it can't move that way.
+    /// Only movements and rotations go anywhere, dropping and holding don't
+    pub fn placement_after(&self, placement: &Placement, action: &Action) -> Option<Placement> {
+        self.step(placement, action).map(|(placement, _)| placement)
+    }
+
+    /// Whether the current shape would lock at the placement, with nothing under it to fall into
+    pub fn is_resting(&self, placement: &Placement) -> bool {
+        !self.fits(placement.rotation, placement.x, placement.y + 1)
+    }
+
+    /// Every distinct position the current shape can lock in from where it is, including slides
+    /// under overhangs and spins into slots. There are none while the next shape is entering
+    pub fn legal_placements(&self) -> Vec<Placement> {
+        self.reachable_locks().into_iter().map(|lock| lock.placement).collect()
+    }
+
+    /// Every distinct position the current shape can lock in, with the actions that get it there,
+    /// ending with the one that locks it. They're the fewest actions, rotating before moving, except
+    /// that a T takes the way that spins it in
+    pub fn legal_moves(&self) -> Vec<(Placement, Vec<Action>)> {
+        self.reachable_locks().into_iter().map(|lock| (lock.placement, lock.actions)).collect()
+    }
+
+    /// Locks the current shape straight into the placement if it can get there, or is invalid if
+    /// it can't or the next shape is still entering
+    pub fn place(&mut self, placement: &Placement) -> ActionResult {
+        let blocks = self.placement_blocks(placement);
+        let reachable = self.reachable_locks().into_iter()
+            .find(|lock| {
+                let reachable_blocks = self.placement_blocks(&lock.placement);
+                blocks.iter().all(|block| reachable_blocks.contains(block))
+            });
+        match reachable {
+            Some(lock) => {
+                self.current_shape_rotations = lock.placement.rotation;
+                self.current_shape_x_diff = lock.placement.x;
+                self.current_shape_y_diff = lock.placement.y;
+                self.last_kick = lock.kick;
+                self.lock_current_shape()
             }
+            None => ActionResult::Invalid,
         }
     }
 
     #[allow(unused_qualifications)]
     pub fn input(&mut self, action: &Action) -> ActionResult {
+        if let (Action::SoftDrop(held), Some(_)) = (action, self.timing) {
+            // the soft drop can be let go of while waiting for the next shape
+            self.soft_dropping = *held;
+            return ActionResult::CurrentShape;
+        }
+        if self.is_entering() {
+            return ActionResult::Invalid;
+        }
         match action {
-            Action::Left => {
-                self.validate_and_place(self.current_shape_rotations, self.current_shape_x_diff - 1, self.current_shape_y_diff)
-            }
-            Action::Right => {
-                self.validate_and_place(self.current_shape_rotations, self.current_shape_x_diff + 1, self.current_shape_y_diff)
-            }
-            Action::Rotate => {
-                self.validate_and_place(self.current_shape_rotations + 1, self.current_shape_x_diff, self.current_shape_y_diff)
+            Action::Left | Action::Right | Action::Rotate | Action::RotateCcw | Action::Rotate180 => {
+                match self.step(&self.current_placement(), action) {
+                    Some((placement, kick)) => self.validate_and_place(placement.rotation, placement.x, placement.y, kick),
+                    None => ActionResult::Invalid,
+                }
             }
-            Action::Down => {
-                self.validate_and_place(self.current_shape_rotations, self.current_shape_x_diff, self.current_shape_y_diff + 1)
+            Action::SoftDrop(false) => ActionResult::CurrentShape,
+            Action::Down | Action::SoftDrop(true) => {
+                match self.step(&self.current_placement(), &Action::Down) {
+                    Some((placement, _)) => {
+                        // only the player soft dropping scores, not gravity
+                        if *action == Action::SoftDrop(true) {
+                            self.scoring.soft_drop(1);
+                        }
+                        self.validate_and_place(placement.rotation, placement.x, placement.y, None)
+                    }
+                    // the shape spawned already resting on the stack
+                    None if self.locks_on_touch() => self.lock_current_shape(),
+                    None => ActionResult::Invalid,
+                }
             }
             Action::Drop => {
-                loop {
-                    let result = self.input(&Down);
-                    if self.current_shape_y_diff == 0 {
-                        return result;
-                    }
+                let rows = self.drop_distance();
+                self.scoring.hard_drop(rows as u32);
+                if rows > 0 {
+                    self.current_shape_y_diff += rows;
+                    self.last_kick = None;
                 }
+                self.lock_current_shape()
+            }
+            Action::Hold => {
+                self.hold()
             }
         }
     }
@@ -313,13 +975,14 @@ impl Tetris {
 
 #[cfg(test)]
 mod tests {
-    use crate::tetris::Action::{Down, Drop, Left, Right, Rotate};
+    use crate::tetris::Action::{Down, Drop, Hold, Left, Right, Rotate, Rotate180, RotateCcw, SoftDrop};
+    use crate::tetris::timing::SpeedCurve;
     use super::*;
 
     fn count_blocks(tetris: &Tetris) -> i32 {
         let mut count = 0;
-        for x in 0..10 {
-            for y in 0..20 {
+        for x in 0..tetris.width() {
+            for y in -tetris.vanish_rows()..tetris.height() {
                 if tetris.block_at(x, y) {
                     count += 1;
                 }
@@ -346,8 +1009,51 @@ mod tests {
         blocks_string
     }
 
+    #[derive(Clone)]
+    struct Only {
+        shape: ShapeKind,
+    }
+
+    impl Randomizer for Only {
+        fn next(&mut self, _: &mut StdRng) -> ShapeKind {
+            self.shape
+        }
+
+        fn box_clone(&self) -> Box<dyn Randomizer> {
+            Box::new(self.clone())
+        }
+    }
+
+    pub fn config_with_only(shape: ShapeKind) -> Config {
+        Config {
+            randomizer: Box::new(Only { shape }),
+            seed: Some(0),
+            rotation_system: RotationSystem::Classic,
+            vanish_rows: 0,
+            ..Config::default()
+        }
+    }
+
+    pub fn timed_config_with_only(shape: ShapeKind) -> Config {
+        Config {
+            timing: Some(Timing::default()),
+            ..config_with_only(shape)
+        }
+    }
+
+    fn tetris_with_only_t_shape(dead_blocks: &[(i8, i8)]) -> Tetris {
+        let mut tetris = Tetris::with_config(Config {
+            rotation_system: RotationSystem::Srs,
+            ..config_with_only(ShapeKind::T)
+        });
+        for (x, y) in dead_blocks {
+            tetris.dead_blocks.fill(&[Block::new(*x, *y); 4], Cell::Garbage);
+        }
+        tetris
+    }
+
     pub fn tetris_with_only_j_shape() -> Tetris {
-        Tetris::new_with_custom_shapes([Shape::j(), Shape::j(), Shape::j(), Shape::j(), Shape::j(), Shape::j(), Shape::j()])
+        Tetris::with_config(config_with_only(ShapeKind::J))
     }
 
     #[test]
@@ -427,6 +1133,38 @@ mod tests {
         assert!(tetris.block_at(4, 2), "\n{}", blocks_as_string(&tetris));
     }
 
+    #[test]
+    fn should_rotate_shape_anticlockwise() {
+        // given
+        let mut tetris = tetris_with_only_j_shape();
+
+        // when / then
+        let result = tetris.input(&RotateCcw);
+
+        assert_eq!(ActionResult::CurrentShape, result);
+        assert_eq!(4, count_blocks(&tetris), "\n{}", blocks_as_string(&tetris));
+        assert!(tetris.block_at(4, 0), "\n{}", blocks_as_string(&tetris));
+        assert!(tetris.block_at(3, 2), "\n{}", blocks_as_string(&tetris));
+        assert!(tetris.block_at(4, 1), "\n{}", blocks_as_string(&tetris));
+        assert!(tetris.block_at(4, 2), "\n{}", blocks_as_string(&tetris));
+    }
+
+    #[test]
+    fn should_rotate_shape_half_a_turn() {
+        // given
+        let mut tetris = tetris_with_only_j_shape();
+
+        // when / then
+        let result = tetris.input(&Rotate180);
+
+        assert_eq!(ActionResult::CurrentShape, result);
+        assert_eq!(4, count_blocks(&tetris), "\n{}", blocks_as_string(&tetris));
+        assert!(tetris.block_at(5, 2), "\n{}", blocks_as_string(&tetris));
+        assert!(tetris.block_at(3, 1), "\n{}", blocks_as_string(&tetris));
+        assert!(tetris.block_at(4, 1), "\n{}", blocks_as_string(&tetris));
+        assert!(tetris.block_at(5, 1), "\n{}", blocks_as_string(&tetris));
+    }
+
     #[test]
     fn should_not_move_shape_left_off_the_grid() {
         // given
@@ -488,20 +1226,44 @@ mod tests {
     }
 
     #[test]
-    fn should_not_move_shape_left_into_dead_blocks() {
+    fn should_kick_shape_off_the_wall_with_srs() {
         // given
-        let mut tetris = tetris_with_only_j_shape();
-        for _ in 0..9 {
-            tetris.input(&Left);
-            tetris.input(&Left);
-            tetris.input(&Left);
-            tetris.input(&Drop);
-        }
-        tetris.input(&Down);
-        tetris.input(&Down);
-
-        // when / then
-        let result = tetris.input(&Left);
+        let mut tetris = Tetris::with_config(Config {
+            rotation_system: RotationSystem::Srs,
+            ..config_with_only(ShapeKind::J)
+        });
+        tetris.input(&Rotate);
+        tetris.input(&Left);
+        tetris.input(&Left);
+        tetris.input(&Left);
+        tetris.input(&Left);
+
+        // when / then
+        let result = tetris.input(&Rotate);
+
+        assert_eq!(ActionResult::CurrentShape, result);
+        assert_eq!(4, count_blocks(&tetris), "\n{}", blocks_as_string(&tetris));
+        assert!(tetris.block_at(0, 1), "\n{}", blocks_as_string(&tetris));
+        assert!(tetris.block_at(1, 1), "\n{}", blocks_as_string(&tetris));
+        assert!(tetris.block_at(2, 1), "\n{}", blocks_as_string(&tetris));
+        assert!(tetris.block_at(2, 2), "\n{}", blocks_as_string(&tetris));
+    }
+
+    #[test]
+    fn should_not_move_shape_left_into_dead_blocks() {
+        // given
+        let mut tetris = tetris_with_only_j_shape();
+        for _ in 0..9 {
+            tetris.input(&Left);
+            tetris.input(&Left);
+            tetris.input(&Left);
+            tetris.input(&Drop);
+        }
+        tetris.input(&Down);
+        tetris.input(&Down);
+
+        // when / then
+        let result = tetris.input(&Left);
 
         assert_eq!(ActionResult::Invalid, result);
         assert_eq!(40, count_blocks(&tetris), "\n{}", blocks_as_string(&tetris));
@@ -575,7 +1337,7 @@ mod tests {
         // when / then
         let result = tetris.input(&Down);
 
-        assert_eq!(ActionResult::NextShape, result);
+        assert!(matches!(result, ActionResult::NextShape(_)), "{:?}", result);
         assert_eq!(8, count_blocks(&tetris), "\n{}", blocks_as_string(&tetris));
         assert!(tetris.block_at(3, 0), "\n{}", blocks_as_string(&tetris));
         assert!(tetris.block_at(3, 1), "\n{}", blocks_as_string(&tetris));
@@ -601,7 +1363,7 @@ mod tests {
         // when / then
         let result = tetris.input(&Down);
 
-        assert_eq!(ActionResult::NextShape, result);
+        assert!(matches!(result, ActionResult::NextShape(_)), "{:?}", result);
         assert_eq!(12, count_blocks(&tetris), "\n{}", blocks_as_string(&tetris));
         assert!(tetris.block_at(3, 0), "\n{}", blocks_as_string(&tetris));
         assert!(tetris.block_at(3, 1), "\n{}", blocks_as_string(&tetris));
@@ -625,7 +1387,7 @@ mod tests {
         // when / then
         let result = tetris.input(&Drop);
 
-        assert_eq!(ActionResult::NextShape, result);
+        assert!(matches!(result, ActionResult::NextShape(_)), "{:?}", result);
         assert_eq!(8, count_blocks(&tetris), "\n{}", blocks_as_string(&tetris));
         assert!(tetris.block_at(3, 0), "\n{}", blocks_as_string(&tetris));
         assert!(tetris.block_at(3, 1), "\n{}", blocks_as_string(&tetris));
@@ -646,7 +1408,7 @@ mod tests {
         // when / then
         let result = tetris.input(&Drop);
 
-        assert_eq!(ActionResult::NextShape, result);
+        assert!(matches!(result, ActionResult::NextShape(_)), "{:?}", result);
         assert_eq!(12, count_blocks(&tetris), "\n{}", blocks_as_string(&tetris));
         assert!(tetris.block_at(3, 0), "\n{}", blocks_as_string(&tetris));
         assert!(tetris.block_at(3, 1), "\n{}", blocks_as_string(&tetris));
@@ -688,7 +1450,7 @@ mod tests {
         // when / then
         let result = tetris.input(&Drop);
 
-        assert_eq!(ActionResult::NextShape, result);
+        assert!(matches!(result, ActionResult::NextShape(_)), "{:?}", result);
         assert_eq!(10, count_blocks(&tetris), "\n{}", blocks_as_string(&tetris));
         assert!(tetris.block_at(3, 0), "\n{}", blocks_as_string(&tetris));
         assert!(tetris.block_at(3, 1), "\n{}", blocks_as_string(&tetris));
@@ -702,17 +1464,890 @@ mod tests {
         assert!(tetris.block_at(9, 19), "\n{}", blocks_as_string(&tetris));
     }
 
+    #[test]
+    fn should_play_the_same_shapes_for_the_same_seed() {
+        // given
+        let mut tetris = Tetris::with_seed(42);
+        let mut same_seed_tetris = Tetris::with_seed(42);
+
+        // when / then
+        for _ in 0..8 {
+            tetris.input(&Drop);
+            same_seed_tetris.input(&Drop);
+            assert_eq!(blocks_as_string(&tetris), blocks_as_string(&same_seed_tetris));
+        }
+    }
+
+    #[test]
+    fn should_keep_the_same_shapes_when_cloned() {
+        // given
+        let mut tetris = Tetris::with_seed(7);
+        for _ in 0..6 {
+            tetris.input(&Drop);
+        }
+        let mut clone = tetris.clone();
+
+        // when / then
+        for _ in 0..3 {
+            tetris.input(&Drop);
+            clone.input(&Drop);
+            assert_eq!(blocks_as_string(&tetris), blocks_as_string(&clone));
+        }
+    }
+
+    #[test]
+    fn should_restore_a_snapshot() {
+        // given
+        let mut tetris = Tetris::with_seed(11);
+        tetris.input(&Drop);
+        tetris.input(&Hold);
+        let snapshot = tetris.snapshot();
+        let blocks = blocks_as_string(&tetris);
+        let next_shapes = tetris.next_shapes();
+        for _ in 0..4 {
+            tetris.input(&Drop);
+        }
+
+        // when
+        tetris.restore(&snapshot);
+
+        // then
+        assert_eq!(blocks, blocks_as_string(&tetris));
+        assert_eq!(next_shapes, tetris.next_shapes());
+        assert_eq!(ActionResult::Invalid, tetris.input(&Hold));
+    }
+
+    #[test]
+    fn should_play_the_same_shapes_after_restoring_a_snapshot() {
+        // given
+        let mut tetris = Tetris::with_seed(13);
+        let snapshot = tetris.snapshot();
+        let mut played = vec![];
+        for _ in 0..10 {
+            tetris.input(&Drop);
+            played.push(blocks_as_string(&tetris));
+        }
+
+        // when
+        tetris.restore(&snapshot);
+
+        // then
+        for blocks in played {
+            tetris.input(&Drop);
+            assert_eq!(blocks, blocks_as_string(&tetris));
+        }
+    }
+
+    #[test]
+    fn should_preview_the_next_shapes() {
+        // given
+        let mut tetris = Tetris::with_seed(3);
+        let next_shapes = tetris.next_shapes();
+
+        // when
+        tetris.input(&Drop);
+
+        // then
+        assert_eq!(5, next_shapes.len());
+        assert_eq!(next_shapes[0], tetris.current_shape());
+        assert_eq!(next_shapes[1..], tetris.next_shapes()[..4]);
+        assert_eq!(5, tetris.next_shapes().len());
+    }
+
+    #[test]
+    fn should_preview_the_configured_number_of_shapes() {
+        // given
+        let tetris = Tetris::with_config(Config {
+            preview_size: 2,
+            ..Config::default()
+        });
+
+        // when / then
+        assert_eq!(2, tetris.next_shapes().len());
+    }
+
+    #[test]
+    fn should_hold_the_current_shape() {
+        // given
+        let mut tetris = Tetris::with_seed(5);
+        let current_shape = tetris.current_shape();
+        let next_shape = tetris.next_shapes()[0];
+        tetris.input(&Left);
+
+        // when
+        let result = tetris.input(&Hold);
+
+        // then
+        assert_eq!(ActionResult::CurrentShape, result);
+        assert_eq!(Some(current_shape), tetris.held_shape());
+        assert_eq!(next_shape, tetris.current_shape());
+        assert_eq!(4, count_blocks(&tetris), "\n{}", blocks_as_string(&tetris));
+        assert_eq!(0, tetris.current_shape_x_diff);
+    }
+
+    #[test]
+    fn should_only_hold_once_per_shape() {
+        // given
+        let mut tetris = Tetris::with_seed(5);
+        tetris.input(&Hold);
+
+        // when
+        let result = tetris.input(&Hold);
+
+        // then
+        assert_eq!(ActionResult::Invalid, result);
+    }
+
+    #[test]
+    fn should_swap_with_the_held_shape_after_the_next_shape() {
+        // given
+        let mut tetris = Tetris::with_seed(5);
+        let first_shape = tetris.current_shape();
+        tetris.input(&Hold);
+        tetris.input(&Drop);
+        let third_shape = tetris.current_shape();
+
+        // when
+        let result = tetris.input(&Hold);
+
+        // then
+        assert_eq!(ActionResult::CurrentShape, result);
+        assert_eq!(first_shape, tetris.current_shape());
+        assert_eq!(Some(third_shape), tetris.held_shape());
+    }
+
+    #[test]
+    fn should_describe_the_locked_shape() {
+        // given
+        let mut tetris = tetris_with_only_j_shape();
+        tetris.input(&Rotate);
+
+        // when
+        let result = tetris.input(&Drop);
+
+        // then
+        assert_eq!(ActionResult::NextShape(Lock {
+            shape: ShapeKind::J,
+            rotation: 1,
+            blocks: [Block::new(4, 17), Block::new(5, 17), Block::new(4, 18), Block::new(4, 19)],
+            cleared_rows: vec![],
+            line_clear: LineClear::None,
+            t_spin: TSpin::None,
+            combo: None,
+            back_to_back: false,
+            perfect_clear: false,
+        }), result);
+    }
+
+    #[test]
+    fn should_describe_the_completed_lines() {
+        // given
+        let mut tetris = tetris_with_only_j_shape();
+        tetris.input(&Left);
+        tetris.input(&Left);
+        tetris.input(&Left);
+        tetris.input(&Drop);
+        tetris.input(&Drop);
+        tetris.input(&Right);
+        tetris.input(&Right);
+        tetris.input(&Right);
+        tetris.input(&Drop);
+        tetris.input(&Rotate);
+        tetris.input(&Rotate);
+        tetris.input(&Right);
+        tetris.input(&Right);
+        tetris.input(&Right);
+        tetris.input(&Right);
+
+        // when
+        let result = tetris.input(&Drop);
+
+        // then
+        match result {
+            ActionResult::NextShape(lock) => {
+                assert_eq!(vec![19], lock.cleared_rows);
+                assert_eq!(LineClear::Single, lock.line_clear);
+            }
+            _ => panic!("{:?}", result),
+        }
+    }
+
+    #[test]
+    fn should_detect_a_t_spin() {
+        // given
+        let mut dead_blocks = vec![(3, 17)];
+        for x in [0, 1, 2, 6, 7, 8, 9] {
+            dead_blocks.push((x, 18));
+        }
+        for x in [0, 1, 2, 3, 5, 6, 7, 8, 9] {
+            dead_blocks.push((x, 19));
+        }
+        let mut tetris = tetris_with_only_t_shape(&dead_blocks);
+        tetris.current_shape_rotations = 1;
+        tetris.current_shape_y_diff = 17;
+        let score = tetris.score();
+
+        // when
+        let result = tetris.input(&Rotate);
+
+        // then
+        match result {
+            ActionResult::NextShape(lock) => {
+                assert_eq!(TSpin::Full, lock.t_spin);
+                assert_eq!(LineClear::Double, lock.line_clear);
+            }
+            _ => panic!("{:?}", result),
+        }
+        assert_eq!(score + 1200, tetris.score());
+    }
+
+    #[test]
+    fn should_detect_a_t_spin_mini() {
+        // given
+        let mut tetris = tetris_with_only_t_shape(&[(0, 17), (0, 19), (2, 19)]);
+        tetris.current_shape_x_diff = -3;
+        tetris.current_shape_y_diff = 17;
+        let score = tetris.score();
+
+        // when
+        let result = tetris.input(&Rotate);
+
+        // then
+        match result {
+            ActionResult::NextShape(lock) => {
+                assert_eq!(TSpin::Mini, lock.t_spin);
+                assert_eq!(LineClear::None, lock.line_clear);
+            }
+            _ => panic!("{:?}", result),
+        }
+        assert_eq!(score + 100, tetris.score());
+    }
+
+    #[test]
+    fn should_only_upgrade_a_mini_after_the_last_quarter_turn_kick() {
+        // given
+        let tetris = tetris_with_only_t_shape(&[(0, 17), (0, 19), (2, 19)]);
+        let placement = Placement { rotation: 1, x: -3, y: 17 };
+
+        // when
+        let quarter_turn = tetris.t_spin(Some(Kick { index: 4, half_turn: false }), &placement);
+        let half_turn = tetris.t_spin(Some(Kick { index: 2, half_turn: true }), &placement);
+
+        // then
+        assert_eq!(TSpin::Full, quarter_turn);
+        assert_eq!(TSpin::Mini, half_turn);
+    }
+
+    #[test]
+    fn should_place_a_t_by_spinning_it_in_when_it_could_also_be_moved_there() {
+        // given
+        let mut tetris = tetris_with_only_t_shape(&[(0, 17), (0, 19), (2, 19)]);
+        tetris.timing = Some(Timing::default());
+
+        // when
+        let result = tetris.place(&Placement { rotation: 1, x: -3, y: 17 });
+
+        // then
+        match result {
+            ActionResult::NextShape(lock) => assert_eq!(TSpin::Mini, lock.t_spin),
+            _ => panic!("{:?}", result),
+        }
+    }
+
+    #[test]
+    fn should_list_a_resting_shape_once_with_timing() {
+        // given
+        let mut tetris = Tetris::with_config(Config {
+            rotation_system: RotationSystem::Srs,
+            ..timed_config_with_only(ShapeKind::I)
+        });
+        tetris.current_shape_y_diff = 18;
+        let start_blocks = tetris.placement_blocks(&tetris.current_placement());
+
+        // when
+        let placements = tetris.legal_placements();
+
+        // then
+        let same_blocks = placements.iter()
+            .filter(|placement| tetris.placement_blocks(placement).iter().all(|block| start_blocks.contains(block)))
+            .count();
+        assert_eq!(1, same_blocks);
+    }
+
+    #[test]
+    fn should_not_detect_a_t_spin_without_a_rotation() {
+        // given
+        let mut tetris = tetris_with_only_t_shape(&[(3, 19), (5, 19)]);
+        tetris.input(&Rotate180);
+
+        // when
+        let result = tetris.input(&Drop);
+
+        // then
+        match result {
+            ActionResult::NextShape(lock) => assert_eq!(TSpin::None, lock.t_spin),
+            _ => panic!("{:?}", result),
+        }
+    }
+
+    #[test]
+    fn should_detect_a_perfect_clear() {
+        // given
+        let mut tetris = tetris_with_only_j_shape();
+        for x in [0, 1, 2, 6, 7, 8, 9] {
+            tetris.dead_blocks.fill(&[Block::new(x, 18), Block::new(x, 19), Block::new(x, 18), Block::new(x, 19)], Cell::Garbage);
+        }
+        tetris.dead_blocks.fill(&[Block::new(3, 19), Block::new(4, 19), Block::new(3, 19), Block::new(4, 19)], Cell::Garbage);
+        tetris.input(&Rotate180);
+
+        // when
+        let result = tetris.input(&Drop);
+
+        // then
+        match result {
+            ActionResult::NextShape(lock) => {
+                assert!(lock.perfect_clear, "\n{}", blocks_as_string(&tetris));
+                assert_eq!(Some(0), lock.combo);
+            }
+            _ => panic!("{:?}", result),
+        }
+        assert_eq!(1, tetris.perfect_clears());
+    }
+
+    #[test]
+    fn should_score_a_completed_line() {
+        // given
+        let mut tetris = tetris_with_only_j_shape();
+        tetris.input(&Left);
+        tetris.input(&Left);
+        tetris.input(&Left);
+        tetris.input(&Drop);
+        tetris.input(&Drop);
+        tetris.input(&Right);
+        tetris.input(&Right);
+        tetris.input(&Right);
+        tetris.input(&Drop);
+        tetris.input(&Rotate);
+        tetris.input(&Rotate);
+        tetris.input(&Right);
+        tetris.input(&Right);
+        tetris.input(&Right);
+        tetris.input(&Right);
+        let score = tetris.score();
+
+        // when
+        tetris.input(&Drop);
+
+        // then
+        assert_eq!(1, tetris.lines());
+        assert_eq!(1, tetris.level());
+        assert_eq!(score + 17 * 2 + 100, tetris.score());
+    }
+
+    #[test]
+    fn should_score_soft_drops() {
+        // given
+        let mut tetris = tetris_with_only_j_shape();
+
+        // when
+        tetris.input(&SoftDrop(true));
+        tetris.input(&SoftDrop(true));
+
+        // then
+        assert_eq!(2, tetris.score());
+    }
+
+    #[test]
+    fn should_not_score_gravity_moving_the_shape_down() {
+        // given
+        let mut tetris = tetris_with_only_j_shape();
+
+        // when
+        tetris.input(&Down);
+
+        // then
+        assert_eq!(1, tetris.current_shape_y_diff);
+        assert_eq!(0, tetris.score());
+    }
+
+    #[test]
+    fn should_score_hard_drops() {
+        // given
+        let mut tetris = tetris_with_only_j_shape();
+
+        // when
+        tetris.input(&Drop);
+
+        // then
+        assert_eq!(18 * 2, tetris.score());
+    }
+
     #[test]
     fn should_complete_a_game() {
         // given
         let mut tetris = tetris_with_only_j_shape();
-        for _ in 0..8 {
+        for _ in 0..9 {
             tetris.input(&Drop);
         }
 
         // when / then
         let result = tetris.input(&Drop);
 
-        assert_eq!(ActionResult::GameOver, result);
+        match result {
+            ActionResult::GameOver(GameOverReason::BlockOut, Some(lock)) => assert_eq!(ShapeKind::J, lock.shape),
+            _ => panic!("expected a block out with the last lock, got {:?}", result),
+        }
+    }
+
+    #[test]
+    fn should_clear_lines_on_a_narrow_board() {
+        // given
+        let mut tetris = Tetris::with_config(Config {
+            width: 4,
+            height: 6,
+            ..config_with_only(ShapeKind::I)
+        });
+
+        // when
+        let result = tetris.input(&Drop);
+
+        // then
+        match result {
+            ActionResult::NextShape(lock) => assert_eq!(vec![5], lock.cleared_rows),
+            _ => panic!("expected a lock, got {:?}", result),
+        }
+        assert!(tetris.block_at(0, 1));
+        assert!(tetris.block_at(3, 1));
+        assert!(!tetris.block_at(0, 5));
+    }
+
+    #[test]
+    fn should_spawn_in_the_middle_and_drop_to_the_floor_of_a_bigger_board() {
+        // given
+        let mut tetris = Tetris::with_config(Config {
+            width: 12,
+            height: 24,
+            ..config_with_only(ShapeKind::J)
+        });
+
+        // when
+        tetris.input(&Drop);
+
+        // then
+        assert_eq!(12, tetris.width());
+        assert_eq!(24, tetris.height());
+        assert!(tetris.block_at(4, 22));
+        assert!(tetris.block_at(4, 23));
+        assert!(tetris.block_at(5, 23));
+        assert!(tetris.block_at(6, 23));
+    }
+
+    #[test]
+    fn should_spawn_above_the_board_and_drop_into_view() {
+        // given
+        let tetris = Tetris::with_config(Config {
+            vanish_rows: 20,
+            ..config_with_only(ShapeKind::J)
+        });
+
+        // when / then
+        assert!(tetris.block_at(3, -1), "\n{}", blocks_as_string(&tetris));
+        assert!(tetris.block_at(3, 0), "\n{}", blocks_as_string(&tetris));
+        assert!(tetris.block_at(4, 0), "\n{}", blocks_as_string(&tetris));
+        assert!(tetris.block_at(5, 0), "\n{}", blocks_as_string(&tetris));
+    }
+
+    #[test]
+    fn should_lock_out_when_a_shape_locks_above_the_board() {
+        // given
+        let mut tetris = Tetris::with_config(Config {
+            height: 4,
+            vanish_rows: 20,
+            ..config_with_only(ShapeKind::J)
+        });
+        tetris.input(&Drop);
+        tetris.input(&Drop);
+
+        // when
+        let result = tetris.input(&Drop);
+
+        // then
+        assert!(matches!(result, ActionResult::GameOver(GameOverReason::LockOut, Some(_))), "{:?}", result);
+    }
+
+    #[test]
+    fn should_lock_a_shape_that_spawned_resting_on_the_stack_when_moved_down() {
+        // given
+        let mut tetris = Tetris::with_config(Config {
+            height: 4,
+            vanish_rows: 20,
+            ..config_with_only(ShapeKind::J)
+        });
+        tetris.input(&Drop);
+        tetris.input(&Drop);
+
+        // when
+        let result = tetris.input(&Down);
+
+        // then
+        assert!(matches!(result, ActionResult::GameOver(GameOverReason::LockOut, Some(_))), "{:?}", result);
+    }
+
+    #[test]
+    fn should_list_every_placement_on_an_empty_board() {
+        // given
+        let j_tetris = Tetris::with_config(Config {
+            rotation_system: RotationSystem::Srs,
+            ..config_with_only(ShapeKind::J)
+        });
+        let o_tetris = Tetris::with_config(config_with_only(ShapeKind::O));
+
+        // when / then
+        assert_eq!(8 + 9 + 8 + 9, j_tetris.legal_placements().len());
+        assert_eq!(9, o_tetris.legal_placements().len());
+    }
+
+    #[test]
+    fn should_list_placements_slid_under_overhangs() {
+        // given
+        let mut tetris = Tetris::with_config(config_with_only(ShapeKind::O));
+        tetris.dead_blocks.fill(&[Block::new(0, 16), Block::new(1, 16), Block::new(0, 19), Block::new(1, 19)], Cell::Garbage);
+
+        // when
+        let placements = tetris.legal_placements();
+
+        // then
+        let tuck = placements.iter()
+            .find(|placement| tetris.placement_blocks(placement).contains(&Block::new(1, 17)));
+        assert!(tuck.is_some(), "{:?}", placements);
+    }
+
+    #[test]
+    fn should_place_a_shape_straight_into_a_legal_placement() {
+        // given
+        let mut tetris = tetris_with_only_j_shape();
+        let placement = tetris.legal_placements().into_iter()
+            .find(|placement| placement.rotation == 1)
+            .unwrap();
+        let blocks = tetris.placement_blocks(&placement);
+
+        // when
+        let result = tetris.place(&placement);
+
+        // then
+        match result {
+            ActionResult::NextShape(lock) => assert_eq!(blocks, lock.blocks),
+            _ => panic!("{:?}", result),
+        }
+    }
+
+    #[test]
+    fn should_place_a_t_by_spinning_it_in_when_it_could_also_slide_there() {
+        // given
+        let mut tetris = tetris_with_only_t_shape(&[(7, 15), (9, 15), (9, 17)]);
+        let placement = Placement { rotation: 2, x: 4, y: 15 };
+        let blocks = tetris.placement_blocks(&placement);
+
+        // when
+        let result = tetris.place(&placement);
+
+        // then
+        match result {
+            ActionResult::NextShape(lock) => {
+                assert_eq!(blocks, lock.blocks);
+                assert_eq!(2, lock.rotation);
+                assert_eq!(TSpin::Mini, lock.t_spin);
+            }
+            _ => panic!("{:?}", result),
+        }
+    }
+
+    #[test]
+    fn should_not_place_a_shape_where_it_cannot_get() {
+        // given
+        let mut tetris = tetris_with_only_j_shape();
+        for x in 0..9 {
+            tetris.dead_blocks.fill(&[Block::new(x, 15); 4], Cell::Garbage);
+        }
+        let buried = Placement { rotation: 0, x: -3, y: 18 };
+        let floating = Placement { rotation: 0, x: 0, y: 5 };
+
+        // when / then
+        assert_eq!(ActionResult::Invalid, tetris.place(&buried));
+        assert_eq!(ActionResult::Invalid, tetris.place(&floating));
+    }
+
+    fn timed_tetris_with_only_j_shape() -> Tetris {
+        Tetris::with_config(Config {
+            timing: Some(Timing {
+                lock_delay: Duration::from_millis(500),
+                lock_resets: 2,
+                entry_delay: Duration::from_millis(100),
+                line_clear_delay: Duration::from_millis(300),
+                soft_drop_multiplier: 20,
+                speed_curve: SpeedCurve::Guideline,
+            }),
+            ..config_with_only(ShapeKind::J)
+        })
+    }
+
+    fn land(tetris: &mut Tetris) {
+        while tetris.input(&Down) == ActionResult::CurrentShape {}
+    }
+
+    #[test]
+    fn should_fall_with_gravity_over_time() {
+        // given
+        let mut tetris = timed_tetris_with_only_j_shape();
+
+        // when
+        tetris.tick(Duration::from_millis(999));
+        let before_a_second = tetris.current_shape_y_diff;
+        tetris.tick(Duration::from_millis(1001));
+
+        // then
+        assert_eq!(0, before_a_second);
+        assert_eq!(2, tetris.current_shape_y_diff);
+    }
+
+    #[test]
+    fn should_lock_after_resting_for_the_lock_delay() {
+        // given
+        let mut tetris = timed_tetris_with_only_j_shape();
+        land(&mut tetris);
+
+        // when
+        let resting = tetris.tick(Duration::from_millis(400));
+        let locked = tetris.tick(Duration::from_millis(100));
+
+        // then
+        assert_eq!(ActionResult::CurrentShape, resting);
+        assert!(matches!(locked, ActionResult::NextShape(_)), "{:?}", locked);
+    }
+
+    #[test]
+    fn should_only_count_the_time_since_landing_towards_the_lock_delay() {
+        // given
+        let mut tetris = timed_tetris_with_only_j_shape();
+        land(&mut tetris);
+        tetris.current_shape_y_diff -= 1;
+
+        // when
+        let result = tetris.tick(Duration::from_millis(1400));
+
+        // then
+        assert_eq!(ActionResult::CurrentShape, result);
+        assert!(tetris.is_resting(&tetris.current_placement()));
+    }
+
+    #[test]
+    fn should_restart_the_lock_delay_when_moved() {
+        // given
+        let mut tetris = timed_tetris_with_only_j_shape();
+        land(&mut tetris);
+        tetris.tick(Duration::from_millis(400));
+
+        // when
+        tetris.input(&Left);
+        let result = tetris.tick(Duration::from_millis(400));
+
+        // then
+        assert_eq!(ActionResult::CurrentShape, result);
+    }
+
+    #[test]
+    fn should_lock_straight_away_when_out_of_lock_resets() {
+        // given
+        let mut tetris = timed_tetris_with_only_j_shape();
+        land(&mut tetris);
+        tetris.input(&Left);
+        tetris.input(&Right);
+
+        // when
+        let result = tetris.input(&Left);
+
+        // then
+        assert!(matches!(result, ActionResult::NextShape(_)), "{:?}", result);
+    }
+
+    #[test]
+    fn should_wait_out_the_entry_delay_before_the_next_shape() {
+        // given
+        let mut tetris = timed_tetris_with_only_j_shape();
+        tetris.input(&Drop);
+
+        // when
+        let waiting = tetris.input(&Left);
+        tetris.tick(Duration::from_millis(100));
+        let entered = tetris.input(&Left);
+
+        // then
+        assert_eq!(ActionResult::Invalid, waiting);
+        assert_eq!(ActionResult::CurrentShape, entered);
+    }
+
+    #[test]
+    fn should_not_place_while_the_next_shape_is_entering() {
+        // given
+        let mut tetris = Tetris::with_config(timed_config_with_only(ShapeKind::J));
+        tetris.input(&Drop);
+        let placement = tetris.current_placement();
+
+        // when
+        let result = tetris.place(&placement);
+
+        // then
+        assert!(tetris.is_entering());
+        assert_eq!(ActionResult::Invalid, result);
+        assert!(tetris.legal_placements().is_empty());
+        assert!(tetris.legal_moves().is_empty());
+    }
+
+    #[test]
+    fn should_wait_longer_for_the_next_shape_after_a_line_clear() {
+        // given
+        let mut tetris = Tetris::with_config(Config {
+            width: 4,
+            height: 6,
+            ..timed_config_with_only(ShapeKind::I)
+        });
+        tetris.input(&Drop);
+
+        // when
+        tetris.tick(Duration::from_millis(300));
+        let waiting = tetris.is_entering();
+        tetris.tick(Duration::from_millis(100));
+
+        // then
+        assert!(waiting);
+        assert!(!tetris.is_entering());
+        assert_eq!(4, count_blocks(&tetris));
+    }
+
+    #[test]
+    fn should_fall_straight_to_the_stack_at_twenty_g() {
+        // given
+        let mut tetris = Tetris::with_config(Config {
+            timing: Some(Timing {
+                speed_curve: SpeedCurve::Tgm,
+                ..Timing::default()
+            }),
+            ..config_with_only(ShapeKind::J)
+        });
+        for _ in 0..125 {
+            tetris.scoring.lock(4, TSpin::None, false);
+        }
+
+        // when
+        tetris.tick(Duration::from_millis(1));
+
+        // then
+        assert_eq!(500, tetris.scoring.tgm_level());
+        assert!(tetris.is_resting(&tetris.current_placement()));
+    }
+
+    #[test]
+    fn should_fall_faster_and_score_while_soft_dropping() {
+        // given
+        let mut tetris = timed_tetris_with_only_j_shape();
+
+        // when
+        tetris.input(&SoftDrop(true));
+        tetris.tick(Duration::from_millis(100));
+        tetris.input(&SoftDrop(false));
+        tetris.tick(Duration::from_millis(100));
+
+        // then
+        assert_eq!(2, tetris.current_shape_y_diff);
+        assert_eq!(2, tetris.score());
+    }
+
+    #[test]
+    fn should_soft_drop_straight_to_the_stack_without_a_multiplier() {
+        // given
+        let mut tetris = Tetris::with_config(Config {
+            timing: Some(Timing { soft_drop_multiplier: 0, ..Timing::default() }),
+            ..config_with_only(ShapeKind::J)
+        });
+
+        // when
+        tetris.input(&SoftDrop(true));
+        let result = tetris.tick(Duration::from_millis(1));
+
+        // then
+        assert_eq!(ActionResult::CurrentShape, result);
+        assert!(tetris.is_resting(&tetris.current_placement()));
+    }
+
+    #[test]
+    fn should_soft_drop_a_row_without_timing() {
+        // given
+        let mut tetris = tetris_with_only_j_shape();
+
+        // when
+        let result = tetris.input(&SoftDrop(true));
+
+        // then
+        assert_eq!(ActionResult::CurrentShape, result);
+        assert_eq!(1, tetris.current_shape_y_diff);
+        assert_eq!(1, tetris.score());
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn should_remember_which_shape_filled_each_cell() {
+        // given
+        let mut tetris = Tetris::with_config(config_with_only(ShapeKind::L));
+        tetris.dead_blocks.fill(&[Block::new(0, 19); 4], Cell::Garbage);
+
+        // when
+        tetris.input(&Drop);
+
+        // then
+        assert_eq!(Cell::L, tetris.cell_at(5, 18));
+        assert_eq!(Cell::L, tetris.cell_at(3, 19));
+        assert_eq!(Cell::Garbage, tetris.cell_at(0, 19));
+        assert_eq!(Cell::Empty, tetris.cell_at(0, 18));
+        assert_eq!(Cell::L, tetris.cell_at(5, 0));
+    }
+
+    #[test]
+    fn should_show_where_the_shape_would_land() {
+        // given
+        let mut tetris = tetris_with_only_j_shape();
+        tetris.input(&Drop);
+        tetris.input(&Left);
+
+        // when
+        let ghost = tetris.ghost();
+
+        // then
+        assert_eq!(16, tetris.drop_distance());
+        assert_eq!(Some([Block::new(2, 16), Block::new(2, 17), Block::new(3, 17), Block::new(4, 17)]), ghost);
+    }
+
+    #[test]
+    fn should_not_show_a_ghost_while_waiting_for_the_next_shape() {
+        // given
+        let mut tetris = timed_tetris_with_only_j_shape();
+
+        // when
+        tetris.input(&Drop);
+
+        // then
+        assert_eq!(None, tetris.ghost());
+    }
+
+    #[test]
+    fn should_show_the_current_shape_apart_from_the_locked_cells() {
+        // given
+        let mut tetris = tetris_with_only_j_shape();
+
+        // when
+        tetris.input(&Drop);
+
+        // then
+        assert_eq!(Some([Block::new(3, 0), Block::new(3, 1), Block::new(4, 1), Block::new(5, 1)]), tetris.active_blocks());
+        assert_eq!(Cell::Empty, tetris.locked_cell_at(3, 0));
+        assert_eq!(Cell::J, tetris.locked_cell_at(3, 18));
+    }
+}
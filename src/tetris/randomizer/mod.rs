@@ -0,0 +1,256 @@
+use rand::rngs::StdRng;
+use rand::Rng;
+use rand::seq::SliceRandom;
+use crate::tetris::ShapeKind;
+
+pub trait Randomizer: Send {
+    fn next(&mut self, rng: &mut StdRng) -> ShapeKind;
+
+    fn box_clone(&self) -> Box<dyn Randomizer>;
+}
+
+impl Clone for Box<dyn Randomizer> {
+    fn clone(&self) -> Self {
+        self.box_clone()
+    }
+}
+
+pub fn from_name(name: &str) -> Option<Box<dyn Randomizer>> {
+    match name {
+        "bag" => Some(Box::new(Bag::new())),
+        "random" => Some(Box::new(Uniform::new())),
+        "nes" => Some(Box::new(Nes::new())),
+        "tgm" => Some(Box::new(Tgm::new())),
+        _ => None,
+    }
+}
+
+/// Deals all seven shapes in a random order before starting a new bag
+#[derive(Clone, Default)]
+pub struct Bag {
+    bag: Vec<ShapeKind>,
+}
+
+impl Bag {
+    pub fn new() -> Bag {
+        Bag { bag: vec![] }
+    }
+}
+
+impl Randomizer for Bag {
+    fn next(&mut self, rng: &mut StdRng) -> ShapeKind {
+        if self.bag.is_empty() {
+            self.bag = ShapeKind::ALL.to_vec();
+            self.bag.shuffle(rng);
+        }
+        self.bag.pop().unwrap()
+    }
+
+    fn box_clone(&self) -> Box<dyn Randomizer> {
+        Box::new(self.clone())
+    }
+}
+
+/// Picks every shape independently
+#[derive(Clone, Default)]
+pub struct Uniform {}
+
+impl Uniform {
+    pub fn new() -> Uniform {
+        Uniform {}
+    }
+}
+
+impl Randomizer for Uniform {
+    fn next(&mut self, rng: &mut StdRng) -> ShapeKind {
+        ShapeKind::ALL[rng.gen_range(0..ShapeKind::ALL.len())]
+    }
+
+    fn box_clone(&self) -> Box<dyn Randomizer> {
+        Box::new(self.clone())
+    }
+}
+
+/// Rolls an eighth "reroll" option alongside the seven shapes, and rerolls once (over the seven
+/// shapes only) when that or the previous shape comes up
+#[derive(Clone, Default)]
+pub struct Nes {
+    previous: Option<ShapeKind>,
+}
+
+impl Nes {
+    pub fn new() -> Nes {
+        Nes { previous: None }
+    }
+}
+
+impl Randomizer for Nes {
+    fn next(&mut self, rng: &mut StdRng) -> ShapeKind {
+        let roll = rng.gen_range(0..ShapeKind::ALL.len() + 1);
+        let shape = if roll == ShapeKind::ALL.len() || Some(ShapeKind::ALL[roll]) == self.previous {
+            ShapeKind::ALL[rng.gen_range(0..ShapeKind::ALL.len())]
+        } else {
+            ShapeKind::ALL[roll]
+        };
+        self.previous = Some(shape);
+        shape
+    }
+
+    fn box_clone(&self) -> Box<dyn Randomizer> {
+        Box::new(self.clone())
+    }
+}
+
+const TGM_ROLLS: usize = 4;
+
+/// Rolls up to four times for a shape that isn't in the history of the last four, starting
+/// from a history of Zs so that the first shape is never an S, Z or O
+#[derive(Clone)]
+pub struct Tgm {
+    history: [ShapeKind; 4],
+    first: bool,
+}
+
+impl Tgm {
+    pub fn new() -> Tgm {
+        Tgm {
+            history: [ShapeKind::Z; 4],
+            first: true,
+        }
+    }
+}
+
+impl Default for Tgm {
+    fn default() -> Self {
+        Tgm::new()
+    }
+}
+
+impl Randomizer for Tgm {
+    fn next(&mut self, rng: &mut StdRng) -> ShapeKind {
+        let shape = if self.first {
+            self.first = false;
+            [ShapeKind::I, ShapeKind::J, ShapeKind::L, ShapeKind::T][rng.gen_range(0..4)]
+        } else {
+            let mut shape = ShapeKind::ALL[rng.gen_range(0..ShapeKind::ALL.len())];
+            for _ in 1..TGM_ROLLS {
+                if !self.history.contains(&shape) {
+                    break;
+                }
+                shape = ShapeKind::ALL[rng.gen_range(0..ShapeKind::ALL.len())];
+            }
+            shape
+        };
+        self.history.rotate_right(1);
+        self.history[0] = shape;
+        shape
+    }
+
+    fn box_clone(&self) -> Box<dyn Randomizer> {
+        Box::new(self.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::SeedableRng;
+    use super::*;
+
+    fn deal(randomizer: &mut dyn Randomizer, count: usize) -> Vec<ShapeKind> {
+        let mut rng = StdRng::seed_from_u64(0);
+        (0..count).map(|_| randomizer.next(&mut rng)).collect()
+    }
+
+    #[test]
+    fn should_deal_every_shape_once_per_bag() {
+        // given
+        let mut bag = Bag::new();
+
+        // when
+        let shapes = deal(&mut bag, 70);
+
+        // then
+        for bag in shapes.chunks(7) {
+            for shape in ShapeKind::ALL {
+                assert_eq!(1, bag.iter().filter(|dealt| **dealt == shape).count(), "{:?}", bag);
+            }
+        }
+    }
+
+    #[test]
+    fn should_shuffle_the_last_shapes_of_a_bag() {
+        // given
+        let mut bag = Bag::new();
+
+        // when
+        let shapes = deal(&mut bag, 700);
+
+        // then
+        for shape in ShapeKind::ALL {
+            assert!(shapes.chunks(7).any(|bag| bag[6] == shape), "{:?} never dealt last", shape);
+        }
+    }
+
+    #[test]
+    fn should_deal_every_shape_at_random() {
+        // given
+        let mut uniform = Uniform::new();
+
+        // when
+        let shapes = deal(&mut uniform, 700);
+
+        // then
+        for shape in ShapeKind::ALL {
+            assert!(shapes.contains(&shape), "{:?} never dealt", shape);
+        }
+    }
+
+    #[test]
+    fn should_repeat_shapes_less_often_with_nes_reroll() {
+        // given
+        let mut nes = Nes::new();
+
+        // when
+        let shapes = deal(&mut nes, 7000);
+
+        // then
+        let repeats = shapes.windows(2).filter(|pair| pair[0] == pair[1]).count();
+        assert!(repeats < 7000 / 14, "{} repeats", repeats);
+    }
+
+    #[test]
+    fn should_not_start_with_s_z_or_o_with_tgm_history() {
+        for seed in 0..50 {
+            // given
+            let mut tgm = Tgm::new();
+            let mut rng = StdRng::seed_from_u64(seed);
+
+            // when
+            let shape = tgm.next(&mut rng);
+
+            // then
+            assert!(![ShapeKind::S, ShapeKind::Z, ShapeKind::O].contains(&shape), "{:?}", shape);
+        }
+    }
+
+    #[test]
+    fn should_repeat_shapes_less_often_with_tgm_history() {
+        // given
+        let mut tgm = Tgm::new();
+
+        // when
+        let shapes = deal(&mut tgm, 7000);
+
+        // then
+        let repeats = shapes.windows(2).filter(|pair| pair[0] == pair[1]).count();
+        assert!(repeats < 7000 / 25, "{} repeats", repeats);
+    }
+
+    #[test]
+    fn should_find_randomizers_by_name() {
+        for name in ["bag", "random", "nes", "tgm"] {
+            assert!(from_name(name).is_some(), "{}", name);
+        }
+        assert!(from_name("unknown").is_none());
+    }
+}
@@ -0,0 +1,125 @@
+use crate::tetris::ShapeKind;
+
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub enum RotationSystem {
+    /// Only tries the rotated position itself
+    Classic,
+    /// The Super Rotation System, which tries a series of offsets ("kicks") to fit a rotation
+    /// against walls and dead blocks
+    Srs,
+}
+
+const NO_KICKS: [(i8, i8); 1] = [(0, 0)];
+
+// Kick offsets from https://tetris.wiki/Super_Rotation_System, with y flipped to point down the
+// grid. Indexed by the rotation being left, then whether it is clockwise or anticlockwise.
+const JLSTZ_KICKS: [[[(i8, i8); 5]; 2]; 4] = [
+    [
+        [(0, 0), (-1, 0), (-1, -1), (0, 2), (-1, 2)],
+        [(0, 0), (1, 0), (1, -1), (0, 2), (1, 2)],
+    ],
+    [
+        [(0, 0), (1, 0), (1, 1), (0, -2), (1, -2)],
+        [(0, 0), (1, 0), (1, 1), (0, -2), (1, -2)],
+    ],
+    [
+        [(0, 0), (1, 0), (1, -1), (0, 2), (1, 2)],
+        [(0, 0), (-1, 0), (-1, -1), (0, 2), (-1, 2)],
+    ],
+    [
+        [(0, 0), (-1, 0), (-1, 1), (0, -2), (-1, -2)],
+        [(0, 0), (-1, 0), (-1, 1), (0, -2), (-1, -2)],
+    ],
+];
+
+const I_KICKS: [[[(i8, i8); 5]; 2]; 4] = [
+    [
+        [(0, 0), (-2, 0), (1, 0), (-2, 1), (1, -2)],
+        [(0, 0), (-1, 0), (2, 0), (-1, -2), (2, 1)],
+    ],
+    [
+        [(0, 0), (-1, 0), (2, 0), (-1, -2), (2, 1)],
+        [(0, 0), (2, 0), (-1, 0), (2, -1), (-1, 2)],
+    ],
+    [
+        [(0, 0), (2, 0), (-1, 0), (2, -1), (-1, 2)],
+        [(0, 0), (1, 0), (-2, 0), (1, 2), (-2, -1)],
+    ],
+    [
+        [(0, 0), (1, 0), (-2, 0), (1, 2), (-2, -1)],
+        [(0, 0), (-2, 0), (1, 0), (-2, 1), (1, -2)],
+    ],
+];
+
+// 180 degree kicks as used by TETR.IO, indexed by the rotation being left
+const HALF_TURN_KICKS: [[(i8, i8); 6]; 4] = [
+    [(0, 0), (0, -1), (1, -1), (-1, -1), (1, 0), (-1, 0)],
+    [(0, 0), (1, 0), (1, -2), (1, -1), (0, -2), (0, -1)],
+    [(0, 0), (0, 1), (-1, 1), (1, 1), (-1, 0), (1, 0)],
+    [(0, 0), (-1, 0), (-1, -2), (-1, -1), (0, -2), (0, -1)],
+];
+
+impl RotationSystem {
+    pub fn from_name(name: &str) -> Option<RotationSystem> {
+        match name {
+            "classic" => Some(RotationSystem::Classic),
+            "srs" => Some(RotationSystem::Srs),
+            _ => None,
+        }
+    }
+
+    /// The offsets to try, in order, when rotating a shape from one rotation to another
+    pub fn kicks(&self, shape: ShapeKind, from: usize, to: usize) -> &'static [(i8, i8)] {
+        let clockwise = to == (from + 1) % 4;
+        let half_turn = to == (from + 2) % 4;
+        match (self, shape) {
+            (RotationSystem::Classic, _) | (RotationSystem::Srs, ShapeKind::O) => &NO_KICKS,
+            (RotationSystem::Srs, _) if half_turn => &HALF_TURN_KICKS[from],
+            (RotationSystem::Srs, ShapeKind::I) => &I_KICKS[from][usize::from(!clockwise)],
+            (RotationSystem::Srs, _) => &JLSTZ_KICKS[from][usize::from(!clockwise)],
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_not_kick_with_the_classic_rotation_system() {
+        for shape in ShapeKind::ALL {
+            assert_eq!([(0, 0)], RotationSystem::Classic.kicks(shape, 0, 1));
+        }
+    }
+
+    #[test]
+    fn should_not_kick_o_shapes() {
+        assert_eq!([(0, 0)], RotationSystem::Srs.kicks(ShapeKind::O, 0, 1));
+        assert_eq!([(0, 0)], RotationSystem::Srs.kicks(ShapeKind::O, 0, 2));
+    }
+
+    #[test]
+    fn should_kick_half_turns_with_their_own_offsets() {
+        for shape in [ShapeKind::I, ShapeKind::T] {
+            for from in 0..4 {
+                let kicks = RotationSystem::Srs.kicks(shape, from, (from + 2) % 4);
+                assert_eq!(6, kicks.len(), "{:?} {}", shape, from);
+                assert_eq!((0, 0), kicks[0], "{:?} {}", shape, from);
+            }
+        }
+    }
+
+    #[test]
+    fn should_kick_back_the_way_a_rotation_came() {
+        for shape in [ShapeKind::I, ShapeKind::T] {
+            for from in 0..4 {
+                let to = (from + 1) % 4;
+                let kicks = RotationSystem::Srs.kicks(shape, from, to);
+                let reverse_kicks = RotationSystem::Srs.kicks(shape, to, from);
+                for (kick, reverse_kick) in kicks.iter().zip(reverse_kicks) {
+                    assert_eq!((-kick.0, -kick.1), *reverse_kick, "{:?} {} -> {}", shape, from, to);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,403 @@
+use crate::tetris::TSpin;
+
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub enum ScoringSystem {
+    Guideline,
+    Nes,
+}
+
+impl ScoringSystem {
+    pub fn from_name(name: &str) -> Option<ScoringSystem> {
+        match name {
+            "guideline" => Some(ScoringSystem::Guideline),
+            "nes" => Some(ScoringSystem::Nes),
+            _ => None,
+        }
+    }
+
+    fn first_level(&self) -> u32 {
+        match self {
+            ScoringSystem::Guideline => 1,
+            ScoringSystem::Nes => 0,
+        }
+    }
+
+    fn lock_points(&self, lines: u32, t_spin: TSpin, level: u32) -> u32 {
+        match (self, t_spin) {
+            (ScoringSystem::Guideline, TSpin::None) => [0, 100, 300, 500, 800][lines as usize] * level,
+            (ScoringSystem::Guideline, TSpin::Mini) => [100, 200, 400][lines as usize] * level,
+            (ScoringSystem::Guideline, TSpin::Full) => [400, 800, 1200, 1600][lines as usize] * level,
+            // the NES had no spins to reward
+            (ScoringSystem::Nes, _) => [0, 40, 100, 300, 1200][lines as usize] * (level + 1),
+        }
+    }
+
+    fn back_to_back_points(&self, points: u32) -> u32 {
+        match self {
+            ScoringSystem::Guideline => points / 2,
+            ScoringSystem::Nes => 0,
+        }
+    }
+
+    fn combo_points(&self, combo: u32, level: u32) -> u32 {
+        match self {
+            ScoringSystem::Guideline => 50 * combo * level,
+            ScoringSystem::Nes => 0,
+        }
+    }
+
+    fn perfect_clear_points(&self, lines: u32, back_to_back: bool, level: u32) -> u32 {
+        match self {
+            ScoringSystem::Guideline if back_to_back && lines == 4 => 3200 * level,
+            ScoringSystem::Guideline => [0, 800, 1200, 1800, 2000][lines as usize] * level,
+            ScoringSystem::Nes => 0,
+        }
+    }
+
+    fn soft_drop_points(&self, rows: u32) -> u32 {
+        rows
+    }
+
+    fn hard_drop_points(&self, rows: u32) -> u32 {
+        match self {
+            ScoringSystem::Guideline => rows * 2,
+            // the NES had no hard drop, so it scores like holding down
+            ScoringSystem::Nes => rows,
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct Scoring {
+    system: ScoringSystem,
+    score: u32,
+    lines: u32,
+    combo: Option<u32>,
+    max_combo: u32,
+    back_to_back_streak: u32,
+    back_to_backs: u32,
+    perfect_clears: u32,
+    tgm_level: u32,
+}
+
+impl Scoring {
+    pub fn new(system: ScoringSystem) -> Scoring {
+        Scoring {
+            system,
+            score: 0,
+            lines: 0,
+            combo: None,
+            max_combo: 0,
+            back_to_back_streak: 0,
+            back_to_backs: 0,
+            perfect_clears: 0,
+            tgm_level: 0,
+        }
+    }
+
+    pub fn score(&self) -> u32 {
+        self.score
+    }
+
+    pub fn lines(&self) -> u32 {
+        self.lines
+    }
+
+    pub fn level(&self) -> u32 {
+        self.system.first_level() + self.levels_up()
+    }
+
+    /// How many levels the game has gone up from the one it started at
+    pub fn levels_up(&self) -> u32 {
+        self.lines / 10
+    }
+
+    /// TGM's own level, which goes up by one for each lock and by the lines each clear, except that
+    /// only clears take it past the last level of each hundred and it stops at 999
+    pub fn tgm_level(&self) -> u32 {
+        self.tgm_level
+    }
+
+    /// How many line clears in a row there have been since the first, or none if the last lock
+    /// didn't clear any lines
+    pub fn combo(&self) -> Option<u32> {
+        self.combo
+    }
+
+    pub fn max_combo(&self) -> u32 {
+        self.max_combo
+    }
+
+    /// How many tetrises and spin clears in a row there have been, unbroken by an easier clear
+    pub fn back_to_back_streak(&self) -> u32 {
+        self.back_to_back_streak
+    }
+
+    /// How many clears have followed a tetris or spin clear in the streak
+    pub fn back_to_backs(&self) -> u32 {
+        self.back_to_backs
+    }
+
+    pub fn perfect_clears(&self) -> u32 {
+        self.perfect_clears
+    }
+
+    pub fn is_back_to_back(&self) -> bool {
+        self.back_to_back_streak > 1
+    }
+
+    pub fn soft_drop(&mut self, rows: u32) {
+        self.score += self.system.soft_drop_points(rows);
+    }
+
+    pub fn hard_drop(&mut self, rows: u32) {
+        self.score += self.system.hard_drop_points(rows);
+    }
+
+    pub fn lock(&mut self, lines: u32, t_spin: TSpin, perfect_clear: bool) {
+        let level = self.level();
+        let mut points = self.system.lock_points(lines, t_spin, level);
+        if lines > 0 {
+            if lines == 4 || t_spin != TSpin::None {
+                self.back_to_back_streak += 1;
+            } else {
+                self.back_to_back_streak = 0;
+            }
+            if self.is_back_to_back() {
+                self.back_to_backs += 1;
+                points += self.system.back_to_back_points(points);
+            }
+            let combo = self.combo.map_or(0, |combo| combo + 1);
+            self.combo = Some(combo);
+            self.max_combo = self.max_combo.max(combo);
+            points += self.system.combo_points(combo, level);
+            if perfect_clear {
+                self.perfect_clears += 1;
+                points += self.system.perfect_clear_points(lines, self.is_back_to_back(), level);
+            }
+        } else {
+            self.combo = None;
+        }
+        self.score += points;
+        self.lines += lines;
+        if lines > 0 {
+            self.tgm_level = (self.tgm_level + lines).min(999);
+        } else if self.tgm_level % 100 != 99 && self.tgm_level < 998 {
+            self.tgm_level += 1;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_start_with_no_score_or_lines() {
+        // given
+        let scoring = Scoring::new(ScoringSystem::Guideline);
+
+        // when / then
+        assert_eq!(0, scoring.score());
+        assert_eq!(0, scoring.lines());
+        assert_eq!(1, scoring.level());
+    }
+
+    #[test]
+    fn should_score_guideline_line_clears_by_level() {
+        // given
+        let mut scoring = Scoring::new(ScoringSystem::Guideline);
+
+        // when
+        scoring.lock(4, TSpin::None, false);
+        scoring.lock(0, TSpin::None, false);
+        scoring.lock(2, TSpin::None, false);
+        scoring.lock(0, TSpin::None, false);
+        scoring.lock(4, TSpin::None, false);
+        scoring.lock(0, TSpin::None, false);
+        scoring.lock(1, TSpin::None, false);
+
+        // then
+        assert_eq!(800 + 300 + 800 + 200, scoring.score());
+        assert_eq!(11, scoring.lines());
+        assert_eq!(2, scoring.level());
+    }
+
+    #[test]
+    fn should_score_nes_line_clears_by_level() {
+        // given
+        let mut scoring = Scoring::new(ScoringSystem::Nes);
+
+        // when
+        scoring.lock(3, TSpin::None, false);
+        scoring.lock(3, TSpin::None, false);
+        scoring.lock(3, TSpin::None, false);
+        scoring.lock(1, TSpin::None, false);
+        scoring.lock(4, TSpin::None, false);
+
+        // then
+        assert_eq!(300 + 300 + 300 + 40 + 2400, scoring.score());
+        assert_eq!(14, scoring.lines());
+        assert_eq!(1, scoring.level());
+    }
+
+    #[test]
+    fn should_score_guideline_t_spins_by_level() {
+        // given
+        let mut scoring = Scoring::new(ScoringSystem::Guideline);
+
+        // when
+        scoring.lock(0, TSpin::Mini, false);
+        scoring.lock(0, TSpin::Full, false);
+        scoring.lock(1, TSpin::Mini, false);
+        scoring.lock(0, TSpin::None, false);
+        scoring.lock(3, TSpin::Full, false);
+        scoring.lock(0, TSpin::None, false);
+        scoring.lock(1, TSpin::None, false);
+        scoring.lock(0, TSpin::None, false);
+        scoring.lock(2, TSpin::Full, false);
+
+        // then
+        assert_eq!(100 + 400 + 200 + 1600 * 3 / 2 + 100 + 1200, scoring.score());
+    }
+
+    #[test]
+    fn should_not_score_nes_t_spins() {
+        // given
+        let mut scoring = Scoring::new(ScoringSystem::Nes);
+
+        // when
+        scoring.lock(0, TSpin::Full, false);
+        scoring.lock(2, TSpin::Full, false);
+
+        // then
+        assert_eq!(100, scoring.score());
+    }
+
+    #[test]
+    fn should_count_and_score_combos() {
+        // given
+        let mut scoring = Scoring::new(ScoringSystem::Guideline);
+
+        // when
+        scoring.lock(1, TSpin::None, false);
+        scoring.lock(1, TSpin::None, false);
+        scoring.lock(2, TSpin::None, false);
+
+        // then
+        assert_eq!(Some(2), scoring.combo());
+        assert_eq!(100 + (100 + 50) + (300 + 100), scoring.score());
+    }
+
+    #[test]
+    fn should_break_combos_on_locks_without_clears() {
+        // given
+        let mut scoring = Scoring::new(ScoringSystem::Guideline);
+        scoring.lock(1, TSpin::None, false);
+        scoring.lock(1, TSpin::None, false);
+
+        // when
+        scoring.lock(0, TSpin::None, false);
+
+        // then
+        assert_eq!(None, scoring.combo());
+        assert_eq!(1, scoring.max_combo());
+    }
+
+    #[test]
+    fn should_count_and_score_back_to_backs() {
+        // given
+        let mut scoring = Scoring::new(ScoringSystem::Guideline);
+
+        // when
+        scoring.lock(4, TSpin::None, false);
+        scoring.lock(0, TSpin::None, false);
+        scoring.lock(2, TSpin::Full, false);
+
+        // then
+        assert!(scoring.is_back_to_back());
+        assert_eq!(2, scoring.back_to_back_streak());
+        assert_eq!(1, scoring.back_to_backs());
+        assert_eq!(800 + 1200 + 600, scoring.score());
+    }
+
+    #[test]
+    fn should_break_back_to_backs_on_easy_clears() {
+        // given
+        let mut scoring = Scoring::new(ScoringSystem::Guideline);
+        scoring.lock(4, TSpin::None, false);
+        scoring.lock(4, TSpin::None, false);
+
+        // when
+        scoring.lock(1, TSpin::None, false);
+
+        // then
+        assert!(!scoring.is_back_to_back());
+        assert_eq!(0, scoring.back_to_back_streak());
+        assert_eq!(1, scoring.back_to_backs());
+    }
+
+    #[test]
+    fn should_count_and_score_perfect_clears() {
+        // given
+        let mut scoring = Scoring::new(ScoringSystem::Guideline);
+
+        // when
+        scoring.lock(2, TSpin::None, true);
+
+        // then
+        assert_eq!(1, scoring.perfect_clears());
+        assert_eq!(300 + 1200, scoring.score());
+    }
+
+    #[test]
+    fn should_track_but_not_score_nes_combos_and_back_to_backs() {
+        // given
+        let mut scoring = Scoring::new(ScoringSystem::Nes);
+
+        // when
+        scoring.lock(4, TSpin::None, false);
+        scoring.lock(4, TSpin::None, true);
+
+        // then
+        assert_eq!(Some(1), scoring.combo());
+        assert_eq!(1, scoring.back_to_backs());
+        assert_eq!(1, scoring.perfect_clears());
+        assert_eq!(1200 + 1200, scoring.score());
+    }
+
+    #[test]
+    fn should_stop_the_tgm_level_at_the_end_of_a_section_until_lines_are_cleared() {
+        // given
+        let mut scoring = Scoring::new(ScoringSystem::Guideline);
+
+        // when
+        for _ in 0..120 {
+            scoring.lock(0, TSpin::None, false);
+        }
+        let stopped = scoring.tgm_level();
+        scoring.lock(2, TSpin::None, false);
+
+        // then
+        assert_eq!(99, stopped);
+        assert_eq!(101, scoring.tgm_level());
+        assert_eq!(0, scoring.levels_up());
+    }
+
+    #[test]
+    fn should_score_drops() {
+        // given
+        let mut guideline = Scoring::new(ScoringSystem::Guideline);
+        let mut nes = Scoring::new(ScoringSystem::Nes);
+
+        // when
+        guideline.soft_drop(3);
+        guideline.hard_drop(10);
+        nes.soft_drop(3);
+        nes.hard_drop(10);
+
+        // then
+        assert_eq!(23, guideline.score());
+        assert_eq!(13, nes.score());
+    }
+}
@@ -0,0 +1,163 @@
+use std::time::Duration;
+use crate::tetris::scoring::Scoring;
+
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub enum SpeedCurve {
+    /// The Guideline's (0.8 - (level - 1) * 0.007) ^ (level - 1) seconds a row, from level 1
+    Guideline,
+    /// The NES's frames a row from level 0, which tops out at one row a frame from level 29
+    Nes,
+    /// TGM's gravity by its own level, which speeds up and briefly slows down again in the third
+    /// section before reaching 20G at 500, where shapes fall to the stack as soon as they appear
+    Tgm,
+}
+
+// The NTSC NES ran at a little over 60 frames a second
+const NES_FRAME_SECONDS: f64 = 1.0 / 60.0988;
+
+const NES_FRAMES_PER_ROW: [u32; 29] = [
+    48, 43, 38, 33, 28, 23, 18, 13, 8, 6,
+    5, 5, 5, 4, 4, 4, 3, 3, 3, 2,
+    2, 2, 2, 2, 2, 2, 2, 2, 2,
+];
+
+// TGM's level and the gravity from then on, in 256ths of a row a frame
+const TGM_GRAVITY: [(u32, u32); 30] = [
+    (0, 4), (30, 6), (35, 8), (40, 10), (50, 12), (60, 16), (70, 32), (80, 48), (90, 64), (100, 80),
+    (120, 96), (140, 112), (160, 128), (170, 144), (200, 4), (220, 32), (230, 64), (233, 96), (236, 128), (239, 160),
+    (243, 192), (247, 224), (251, 256), (300, 512), (330, 768), (360, 1024), (400, 1280), (420, 1024), (450, 768), (500, 5120),
+];
+
+const TGM_TWENTY_G: u32 = 20 * 256;
+
+impl SpeedCurve {
+    pub fn from_name(name: &str) -> Option<SpeedCurve> {
+        match name {
+            "guideline" => Some(SpeedCurve::Guideline),
+            "nes" => Some(SpeedCurve::Nes),
+            "tgm" => Some(SpeedCurve::Tgm),
+            _ => None,
+        }
+    }
+
+    /// How long a shape takes to fall a row, which is no time at all at 20G. The Guideline and NES
+    /// curves go by the levels gone up from the first, whatever level the scoring starts at, and
+    /// TGM's by its own level
+    pub fn row_time(&self, levels_up: u32, tgm_level: u32) -> Duration {
+        match self {
+            SpeedCurve::Guideline => {
+                let level = f64::from(levels_up.min(19));
+                Duration::from_secs_f64((0.8 - level * 0.007).powf(level))
+            }
+            SpeedCurve::Nes => {
+                let frames = NES_FRAMES_PER_ROW.get(levels_up as usize).copied().unwrap_or(1);
+                Duration::from_secs_f64(f64::from(frames) * NES_FRAME_SECONDS)
+            }
+            SpeedCurve::Tgm => {
+                let gravity = TGM_GRAVITY.iter()
+                    .rev()
+                    .find(|(from_level, _)| tgm_level >= *from_level)
+                    .map(|(_, gravity)| *gravity)
+                    .unwrap();
+                if gravity >= TGM_TWENTY_G {
+                    Duration::ZERO
+                } else {
+                    Duration::from_secs_f64(256.0 / (60.0 * f64::from(gravity)))
+                }
+            }
+        }
+    }
+}
+
+/// How long things take when a game is played in real time rather than move by move
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub struct Timing {
+    /// How long a shape can rest on the stack before it locks
+    pub lock_delay: Duration,
+    /// How many times moving or rotating a resting shape can restart its lock delay, until it
+    /// falls to a lower row than it has been before
+    pub lock_resets: u32,
+    /// How long after a lock before the next shape appears (ARE)
+    pub entry_delay: Duration,
+    /// How much longer the next shape takes to appear when the lock cleared lines
+    pub line_clear_delay: Duration,
+    /// How many times faster shapes fall while the soft drop is held, where none drops them
+    /// straight to the stack
+    pub soft_drop_multiplier: u32,
+    /// How gravity speeds up with the level
+    pub speed_curve: SpeedCurve,
+}
+
+impl Default for Timing {
+    fn default() -> Self {
+        Timing {
+            lock_delay: Duration::from_millis(500),
+            lock_resets: 15,
+            entry_delay: Duration::from_millis(100),
+            line_clear_delay: Duration::from_millis(300),
+            soft_drop_multiplier: 20,
+            speed_curve: SpeedCurve::Guideline,
+        }
+    }
+}
+
+impl Timing {
+    /// How long a shape takes to fall a row as far into the game as the scoring has got
+    pub fn gravity(&self, scoring: &Scoring) -> Duration {
+        self.speed_curve.row_time(scoring.levels_up(), scoring.tgm_level())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::tetris::scoring::ScoringSystem;
+    use super::*;
+
+    #[test]
+    fn should_fall_faster_at_higher_guideline_levels() {
+        // given
+        let curve = SpeedCurve::Guideline;
+
+        // when / then
+        assert_eq!(Duration::from_secs(1), curve.row_time(0, 0));
+        assert_eq!(Duration::from_secs_f64(0.793), curve.row_time(1, 0));
+        assert!(curve.row_time(14, 0) < Duration::from_millis(10));
+        assert_eq!(curve.row_time(19, 0), curve.row_time(29, 0));
+    }
+
+    #[test]
+    fn should_fall_by_nes_frames_per_row() {
+        // given
+        let curve = SpeedCurve::Nes;
+
+        // when / then
+        assert_eq!(Duration::from_secs_f64(48.0 * NES_FRAME_SECONDS), curve.row_time(0, 0));
+        assert_eq!(Duration::from_secs_f64(6.0 * NES_FRAME_SECONDS), curve.row_time(9, 0));
+        assert_eq!(Duration::from_secs_f64(2.0 * NES_FRAME_SECONDS), curve.row_time(28, 0));
+        assert_eq!(Duration::from_secs_f64(NES_FRAME_SECONDS), curve.row_time(29, 0));
+    }
+
+    #[test]
+    fn should_start_each_curve_at_its_first_level_whatever_the_scoring() {
+        // given
+        let scoring = Scoring::new(ScoringSystem::Guideline);
+
+        // when / then
+        assert_eq!(SpeedCurve::Nes.row_time(0, 0), Timing { speed_curve: SpeedCurve::Nes, ..Timing::default() }.gravity(&scoring));
+        assert_eq!(SpeedCurve::Tgm.row_time(0, 0), Timing { speed_curve: SpeedCurve::Tgm, ..Timing::default() }.gravity(&scoring));
+        assert_eq!(Duration::from_secs(1), Timing::default().gravity(&scoring));
+    }
+
+    #[test]
+    fn should_reach_twenty_g_on_the_tgm_curve() {
+        // given
+        let curve = SpeedCurve::Tgm;
+
+        // when / then
+        assert_eq!(Duration::from_secs_f64(256.0 / (60.0 * 4.0)), curve.row_time(0, 0));
+        assert_eq!(Duration::from_secs_f64(256.0 / (60.0 * 6.0)), curve.row_time(0, 30));
+        assert!(curve.row_time(0, 200) > curve.row_time(0, 199), "gravity slows back down at level 200");
+        assert!(curve.row_time(0, 499) > Duration::ZERO);
+        assert_eq!(Duration::ZERO, curve.row_time(0, 500));
+    }
+}